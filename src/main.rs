@@ -1,20 +1,19 @@
 // disable console opening on windows
 #![windows_subsystem = "windows"]
 
-use bevy::prelude::{App, Msaa, WindowDescriptor};
+use bevy::prelude::{App, Msaa};
 use bevy::DefaultPlugins;
-use the_cleanup_plugin::GamePlugin;
+use the_cleanup_plugin::{GamePlugin, Settings};
 
 fn main() {
+    let settings = Settings::load();
     let mut app = App::build();
-    app.insert_resource(Msaa { samples: 4 })
-        .insert_resource(WindowDescriptor {
-            width: 800.,
-            height: 600.,
-            title: "The Cleanup".to_string(),
-            ..Default::default()
-        })
-        .add_plugins(DefaultPlugins)
-        .add_plugin(GamePlugin)
-        .run();
+    app.insert_resource(Msaa {
+        samples: settings.msaa_samples,
+    })
+    .insert_resource(settings.window_descriptor())
+    .insert_resource(settings)
+    .add_plugins(DefaultPlugins)
+    .add_plugin(GamePlugin)
+    .run();
 }
@@ -1,14 +1,28 @@
 use crate::digger::DiggerState;
+use crate::settings::Settings;
 use crate::GameState;
 use bevy::prelude::*;
 
 pub struct ActionsPlugin;
 
+#[derive(SystemLabel, Eq, PartialEq, Hash, Clone, Debug)]
+pub enum ActionsSystemLabels {
+    SetActions,
+}
+
 impl Plugin for ActionsPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.init_resource::<Actions>().add_system_set(
-            SystemSet::on_update(GameState::Playing).with_system(set_movement_actions.system()),
-        );
+        app.init_resource::<Actions>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(
+                    set_movement_actions
+                        .system()
+                        .label(ActionsSystemLabels::SetActions),
+                ),
+            )
+            .add_system_set(
+                SystemSet::on_pause(GameState::Playing).with_system(reset_actions.system()),
+            );
     }
 }
 
@@ -17,55 +31,66 @@ pub struct Actions {
     pub player_movement: Option<f32>,
     pub flying: bool,
     pub mining_down: bool,
+    /// Only set in the frame the upgrade key was pressed
+    pub upgrade: bool,
+}
+
+/// Keys released while the game is paused would otherwise never be noticed
+fn reset_actions(mut actions: ResMut<Actions>) {
+    *actions = Actions::default();
 }
 
 fn set_movement_actions(
     mut actions: ResMut<Actions>,
     keyboard_input: Res<Input<KeyCode>>,
     state: Res<DiggerState>,
+    settings: Res<Settings>,
 ) {
-    if state.dead || state.waste == 10 {
+    if state.dead {
         actions.flying = false;
         actions.mining_down = false;
         actions.player_movement = None;
+        actions.upgrade = false;
         return;
     }
+    let keys = settings.control_scheme.keys();
+    actions.upgrade = keyboard_input.just_pressed(keys.upgrade);
 
-    if keyboard_input.just_released(KeyCode::W)
-        || keyboard_input.just_pressed(KeyCode::W)
-        || keyboard_input.just_released(KeyCode::A)
-        || keyboard_input.just_released(KeyCode::D)
-        || keyboard_input.pressed(KeyCode::A)
-        || keyboard_input.pressed(KeyCode::D)
+    if keyboard_input.just_released(keys.up)
+        || keyboard_input.just_pressed(keys.up)
+        || keyboard_input.just_released(keys.left)
+        || keyboard_input.just_released(keys.right)
+        || keyboard_input.pressed(keys.left)
+        || keyboard_input.pressed(keys.right)
     {
         actions.mining_down = false;
         let mut player_movement = actions.player_movement.unwrap_or(0.);
 
-        if keyboard_input.just_released(KeyCode::W) {
+        if keyboard_input.just_released(keys.up) {
             actions.flying = false;
-        } else if keyboard_input.just_pressed(KeyCode::W) {
+        } else if keyboard_input.just_pressed(keys.up) {
             actions.flying = true;
         }
 
-        if keyboard_input.just_released(KeyCode::D) || keyboard_input.just_released(KeyCode::A) {
-            if keyboard_input.pressed(KeyCode::D) {
+        if keyboard_input.just_released(keys.right) || keyboard_input.just_released(keys.left) {
+            if keyboard_input.pressed(keys.right) {
                 player_movement = 1.;
-            } else if keyboard_input.pressed(KeyCode::A) {
+            } else if keyboard_input.pressed(keys.left) {
                 player_movement = -1.;
             } else {
                 player_movement = 0.;
             }
-        } else if keyboard_input.just_pressed(KeyCode::D) {
+        } else if keyboard_input.just_pressed(keys.right) {
             player_movement = 1.;
-        } else if keyboard_input.just_pressed(KeyCode::A) {
+        } else if keyboard_input.just_pressed(keys.left) {
             player_movement = -1.;
         }
 
         actions.player_movement = Some(player_movement);
     } else {
         actions.player_movement = None;
-        if keyboard_input.just_released(KeyCode::S) || keyboard_input.just_pressed(KeyCode::S) {
-            actions.mining_down = keyboard_input.just_pressed(KeyCode::S);
+        if keyboard_input.just_released(keys.down) || keyboard_input.just_pressed(keys.down) {
+            actions.mining_down = keyboard_input.just_pressed(keys.down);
         }
     }
 }
@@ -1,65 +1,333 @@
 use crate::actions::Actions;
-use crate::digger::{DiggerState, FuelUpgrade, WasteCollected};
+use crate::base::LampUpgraded;
+use crate::digger::{DiggerState, FuelUpgrade, TileMined, WasteCollected};
 use crate::loading::AudioAssets;
+use crate::map::{Map, Tile};
+use crate::navigation::ButtonActivated;
+use crate::rules::{GameLost, GameWon};
+use crate::settings::{Settings, VolumeSetting};
+use crate::warnings::Warning;
 use crate::GameState;
 use bevy::prelude::*;
-use bevy_kira_audio::{Audio, AudioChannel, AudioPlugin};
+use bevy_kira_audio::{Audio, AudioChannel, AudioPlugin, AudioSource};
 
 pub struct InternalAudioPlugin;
 
 impl Plugin for InternalAudioPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.insert_resource(AudioChannels {
-            digging: AudioChannel::new("digging".to_owned()),
-            flying: AudioChannel::new("flying".to_owned()),
-        })
-        .add_plugin(AudioPlugin)
-        .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(start_audio.system()))
-        .add_system_set(
-            SystemSet::on_update(GameState::Playing)
-                .with_system(play_flying_and_digging_sounds.system())
-                .with_system(collect_waste.system())
-                .with_system(collect_fuel.system()),
-        )
-        .add_system_set(SystemSet::on_exit(GameState::Playing).with_system(stop_audio.system()));
+        app.insert_resource(AudioBuses::default())
+            .add_plugin(AudioPlugin)
+            .add_system(toggle_mute.system())
+            .add_system(apply_volumes.system())
+            .add_system(play_ui_sounds.system())
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(start_audio.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(play_flying_sound.system())
+                    .with_system(play_drilling_sound.system())
+                    .with_system(break_tile.system())
+                    .with_system(collect_waste.system())
+                    .with_system(collect_fuel.system())
+                    .with_system(upgrade_lamp.system())
+                    .with_system(warn.system())
+                    .with_system(reload_loops.system())
+                    .with_system(end_of_run.system()),
+            )
+            .add_system_set(
+                SystemSet::on_pause(GameState::Playing).with_system(pause_audio.system()),
+            )
+            .add_system_set(
+                SystemSet::on_resume(GameState::Playing).with_system(resume_audio.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Playing).with_system(stop_audio.system()),
+            );
     }
 }
 
-struct AudioChannels {
+/// The loops are quieter than the one shot effects
+const LOOP_VOLUME: f32 = 0.3;
+/// Volume of the loops while an effect plays
+const DUCKED_VOLUME: f32 = 0.4;
+/// How long the loops stay ducked after an effect started
+const DUCK_SECONDS: f32 = 0.6;
+/// How fast the loops are ducked and come back
+const DUCK_SPEED: f32 = 8.;
+const MUTE_KEY: KeyCode = KeyCode::M;
+/// Share the drilling sound speeds up by until the tile breaks
+const DRILL_PITCH_RISE: f32 = 0.2;
+
+/// Every sound plays on one bus; each bus has its own volume setting
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AudioBus {
+    Sfx,
+    Ambience,
+    Ui,
+}
+
+/// The kira channels of all buses but music
+///
+/// The flying and digging loops belong to the sfx bus, but have their own channels
+/// to be paused and ducked independently from the one shot effects.
+/// Music is mixed from layers on their own channels by the music plugin.
+pub struct AudioBuses {
+    effects: AudioChannel,
     flying: AudioChannel,
     digging: AudioChannel,
+    ambience: AudioChannel,
+    ui: AudioChannel,
+    /// Tile the drilling sound currently playing in the digging channel belongs to
+    drilling: Option<Tile>,
+    /// Playback rate last sent to the digging channel, `None` while it has to be resumed
+    drilling_rate: Option<f32>,
+    duck_timer: f32,
+    duck_level: f32,
+    /// Volumes last sent to kira, in the order of [`AudioBuses::volumes`]
+    applied_volumes: Vec<f32>,
+}
+
+impl Default for AudioBuses {
+    fn default() -> Self {
+        AudioBuses {
+            effects: AudioChannel::new("effects".to_owned()),
+            flying: AudioChannel::new("flying".to_owned()),
+            digging: AudioChannel::new("digging".to_owned()),
+            ambience: AudioChannel::new("ambience".to_owned()),
+            ui: AudioChannel::new("ui".to_owned()),
+            drilling: None,
+            drilling_rate: None,
+            duck_timer: 0.,
+            duck_level: 1.,
+            applied_volumes: vec![],
+        }
+    }
 }
 
-fn start_audio(audio_assets: Res<AudioAssets>, audio: Res<Audio>, channels: Res<AudioChannels>) {
-    audio.set_volume_in_channel(0.3, &channels.flying);
-    audio.set_volume_in_channel(0.3, &channels.digging);
-    audio.play_looped_in_channel(audio_assets.flying.clone(), &channels.flying);
-    audio.play_looped_in_channel(audio_assets.digging.clone(), &channels.digging);
-    audio.pause_channel(&channels.flying);
-    audio.pause_channel(&channels.digging);
+impl AudioBuses {
+    pub fn channel(&self, bus: AudioBus) -> &AudioChannel {
+        match bus {
+            AudioBus::Sfx => &self.effects,
+            AudioBus::Ambience => &self.ambience,
+            AudioBus::Ui => &self.ui,
+        }
+    }
+
+    /// Plays a sound once; effects duck the flying and digging loops for a moment
+    pub fn play(&mut self, audio: &Audio, bus: AudioBus, source: Handle<AudioSource>) {
+        audio.play_in_channel(source, self.channel(bus));
+        if bus == AudioBus::Sfx {
+            self.duck_timer = DUCK_SECONDS;
+        }
+    }
+
+    fn volumes(&self, settings: &Settings) -> Vec<(&AudioChannel, f32)> {
+        let loop_volume = LOOP_VOLUME * self.duck_level * settings.volume(VolumeSetting::Sfx);
+        vec![
+            (&self.effects, settings.volume(VolumeSetting::Sfx)),
+            (&self.flying, loop_volume),
+            (&self.digging, loop_volume),
+            (&self.ambience, settings.volume(VolumeSetting::Ambience)),
+            (&self.ui, settings.volume(VolumeSetting::Ui)),
+        ]
+    }
 }
 
-fn stop_audio(audio: Res<Audio>, channels: Res<AudioChannels>) {
-    audio.stop_channel(&channels.flying);
-    audio.stop_channel(&channels.digging);
+fn toggle_mute(keyboard_input: Res<Input<KeyCode>>, mut settings: ResMut<Settings>) {
+    if keyboard_input.just_pressed(MUTE_KEY) {
+        settings.muted = !settings.muted;
+        settings.save();
+    }
 }
 
-fn play_flying_and_digging_sounds(
-    digger_state: Res<DiggerState>,
+/// Keeps the channel volumes in sync with the settings and the ducking of the loops
+fn apply_volumes(
+    time: Res<Time>,
+    audio: Res<Audio>,
+    settings: Res<Settings>,
+    mut buses: ResMut<AudioBuses>,
+) {
+    let delta = time.delta_seconds();
+    buses.duck_timer = (buses.duck_timer - delta).max(0.);
+    let duck_target = if buses.duck_timer > 0. {
+        DUCKED_VOLUME
+    } else {
+        1.
+    };
+    buses.duck_level += (duck_target - buses.duck_level) * (1. - (-DUCK_SPEED * delta).exp());
+
+    let mut applied_volumes = vec![];
+    for (index, (channel, volume)) in buses.volumes(&settings).into_iter().enumerate() {
+        match buses.applied_volumes.get(index) {
+            Some(applied) if (applied - volume).abs() <= 0.005 => applied_volumes.push(*applied),
+            _ => {
+                audio.set_volume_in_channel(volume, channel);
+                applied_volumes.push(volume);
+            }
+        }
+    }
+    buses.applied_volumes = applied_volumes;
+}
+
+fn play_ui_sounds(
+    audio: Res<Audio>,
+    audio_assets: Option<Res<AudioAssets>>,
+    mut buses: ResMut<AudioBuses>,
+    mut activated: EventReader<ButtonActivated>,
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<Button>)>,
+) {
+    let audio_assets = match audio_assets {
+        Some(audio_assets) => audio_assets,
+        None => return,
+    };
+    // buttons activated with the keyboard or a gamepad click as well
+    let clicks = interaction_query
+        .iter()
+        .filter(|interaction| **interaction == Interaction::Clicked)
+        .count()
+        + activated.iter().count();
+    for _ in 0..clicks {
+        buses.play(&audio, AudioBus::Ui, audio_assets.get("click"));
+    }
+}
+
+fn start_audio(audio_assets: Res<AudioAssets>, audio: Res<Audio>, buses: Res<AudioBuses>) {
+    audio.play_looped_in_channel(audio_assets.get("flying"), &buses.flying);
+    audio.play_looped_in_channel(audio_assets.get("ambience"), &buses.ambience);
+    audio.pause_channel(&buses.flying);
+}
+
+fn stop_audio(audio: Res<Audio>, mut buses: ResMut<AudioBuses>) {
+    audio.stop_channel(&buses.flying);
+    audio.stop_channel(&buses.digging);
+    audio.stop_channel(&buses.ambience);
+    buses.drilling = None;
+}
+
+fn pause_audio(audio: Res<Audio>, mut buses: ResMut<AudioBuses>) {
+    audio.pause_channel(&buses.effects);
+    audio.pause_channel(&buses.flying);
+    audio.pause_channel(&buses.digging);
+    audio.pause_channel(&buses.ambience);
+    buses.drilling_rate = None;
+}
+
+fn resume_audio(audio: Res<Audio>, buses: Res<AudioBuses>) {
+    // the loops are resumed as soon as the digger flies or digs again
+    audio.resume_channel(&buses.effects);
+    audio.resume_channel(&buses.ambience);
+}
+
+/// Restarts the loops whose sound changed on disk
+fn reload_loops(
+    mut events: EventReader<AssetEvent<AudioSource>>,
     actions: Res<Actions>,
+    map: Res<Map>,
+    digger_state: Res<DiggerState>,
     audio: Res<Audio>,
-    channels: Res<AudioChannels>,
+    audio_assets: Res<AudioAssets>,
+    mut buses: ResMut<AudioBuses>,
 ) {
+    for event in events.iter() {
+        let handle = match event {
+            AssetEvent::Modified { handle } => handle,
+            _ => continue,
+        };
+        if *handle == audio_assets.get("flying") {
+            audio.stop_channel(&buses.flying);
+            audio.play_looped_in_channel(handle.clone(), &buses.flying);
+            if !actions.flying {
+                audio.pause_channel(&buses.flying);
+            }
+        }
+        if *handle == audio_assets.get("ambience") {
+            audio.stop_channel(&buses.ambience);
+            audio.play_looped_in_channel(handle.clone(), &buses.ambience);
+        }
+        if let Some((x, y)) = digger_state.mining_target {
+            if *handle == drill_sound(&audio_assets, &map.tiles[y][x]).0 {
+                // play_drilling_sound starts the loop again
+                audio.stop_channel(&buses.digging);
+                buses.drilling = None;
+            }
+        }
+    }
+}
+
+fn play_flying_sound(actions: Res<Actions>, audio: Res<Audio>, buses: Res<AudioBuses>) {
     if actions.flying {
-        audio.resume_channel(&channels.flying);
+        audio.resume_channel(&buses.flying);
     } else {
-        audio.pause_channel(&channels.flying)
+        audio.pause_channel(&buses.flying)
     }
+}
 
-    if digger_state.mining_target.is_some() {
-        audio.resume_channel(&channels.digging);
-    } else {
-        audio.pause_channel(&channels.digging);
+/// Loop and playback rate of the drilling sound for a tile
+///
+/// Tiles sharing a sound are told apart by their rates. Every rate is more than
+/// `1 + DRILL_PITCH_RISE` times the next lower one, so the pitch rising with the mining progress
+/// never reaches the pitch of another tile.
+fn drill_sound(audio_assets: &AudioAssets, tile: &Tile) -> (Handle<AudioSource>, f32) {
+    match tile {
+        Tile::Silver => (audio_assets.get("digging"), 1.),
+        Tile::Gold => (audio_assets.get("digging"), 1.25),
+        Tile::Diamond => (audio_assets.get("drill_crystal"), 1.),
+        Tile::Waste => (audio_assets.get("drill_waste"), 1.),
+        Tile::TankUpgrade => (audio_assets.get("digging"), 1.55),
+        _ => (audio_assets.get("digging"), 0.8),
+    }
+}
+
+/// Plays the drilling sound of the mined tile, getting higher as the tile is about to break
+fn play_drilling_sound(
+    digger_state: Res<DiggerState>,
+    map: Res<Map>,
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+    mut buses: ResMut<AudioBuses>,
+) {
+    let tile = match digger_state.mining_target {
+        Some((x, y)) => map.tiles[y][x].clone(),
+        None => {
+            if buses.drilling.take().is_some() {
+                audio.stop_channel(&buses.digging);
+            }
+            return;
+        }
+    };
+    let (source, rate) = drill_sound(&audio_assets, &tile);
+    if buses.drilling.as_ref() != Some(&tile) {
+        audio.stop_channel(&buses.digging);
+        audio.play_looped_in_channel(source, &buses.digging);
+        buses.drilling = Some(tile.clone());
+        buses.drilling_rate = None;
+    }
+    let progress = (digger_state.mining / tile.mining_strength().unwrap_or(1.)).clamp(0., 1.);
+    let rate = rate * (1. + DRILL_PITCH_RISE * progress);
+    match buses.drilling_rate {
+        Some(applied) if (applied - rate).abs() <= 0.005 => (),
+        Some(_) => {
+            audio.set_playback_rate_in_channel(rate, &buses.digging);
+            buses.drilling_rate = Some(rate);
+        }
+        None => {
+            // the channel is paused while the game is
+            audio.resume_channel(&buses.digging);
+            audio.set_playback_rate_in_channel(rate, &buses.digging);
+            buses.drilling_rate = Some(rate);
+        }
+    }
+}
+
+fn break_tile(
+    mut events: EventReader<TileMined>,
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+    mut buses: ResMut<AudioBuses>,
+) {
+    for _event in events.iter() {
+        buses.play(&audio, AudioBus::Sfx, audio_assets.get("tile_break"));
     }
 }
 
@@ -67,9 +335,10 @@ fn collect_waste(
     mut events: EventReader<WasteCollected>,
     audio: Res<Audio>,
     audio_assets: Res<AudioAssets>,
+    mut buses: ResMut<AudioBuses>,
 ) {
     for _event in events.iter() {
-        audio.play(audio_assets.waste.clone());
+        buses.play(&audio, AudioBus::Sfx, audio_assets.get("waste"));
     }
 }
 
@@ -77,8 +346,46 @@ fn collect_fuel(
     mut events: EventReader<FuelUpgrade>,
     audio: Res<Audio>,
     audio_assets: Res<AudioAssets>,
+    mut buses: ResMut<AudioBuses>,
+) {
+    for _event in events.iter() {
+        buses.play(&audio, AudioBus::Sfx, audio_assets.get("fuel"));
+    }
+}
+
+fn upgrade_lamp(
+    mut events: EventReader<LampUpgraded>,
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+    mut buses: ResMut<AudioBuses>,
 ) {
     for _event in events.iter() {
-        audio.play(audio_assets.fuel.clone());
+        buses.play(&audio, AudioBus::Sfx, audio_assets.get("fuel"));
+    }
+}
+
+fn warn(
+    mut events: EventReader<Warning>,
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+    mut buses: ResMut<AudioBuses>,
+) {
+    // warnings of the same frame share one sound
+    if events.iter().count() > 0 {
+        buses.play(&audio, AudioBus::Sfx, audio_assets.get("warning"));
+    }
+}
+
+fn end_of_run(
+    mut game_won: EventReader<GameWon>,
+    mut game_lost: EventReader<GameLost>,
+    audio: Res<Audio>,
+    mut buses: ResMut<AudioBuses>,
+) {
+    if game_won.iter().count() + game_lost.iter().count() > 0 {
+        audio.stop_channel(&buses.flying);
+        audio.stop_channel(&buses.digging);
+        audio.stop_channel(&buses.ambience);
+        buses.drilling = None;
     }
 }
@@ -1,31 +1,47 @@
 use bevy::prelude::*;
 
+use crate::actions::{Actions, ActionsSystemLabels};
 use crate::digger::{Digger, DiggerState};
 use crate::map::Map;
 use crate::GameState;
 
+pub const LAMP_UPGRADE_PRICE: f32 = 30.;
+const LAMP_UPGRADE_RADIUS: f32 = 1.;
+pub const MAX_LIGHT_RADIUS: f32 = 8.;
+
 pub struct BasePlugin;
 
 #[derive(SystemLabel, Eq, PartialEq, Hash, Clone, Debug)]
 pub enum BaseSystemLabels {
     CheckPlayerPosition,
+    FuelUp,
 }
 
 impl Plugin for BasePlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.insert_resource(Base { active: false }).add_system_set(
-            SystemSet::on_update(GameState::Playing)
-                .with_system(
-                    check_player_position
-                        .system()
-                        .label(BaseSystemLabels::CheckPlayerPosition),
-                )
-                .with_system(
-                    fuel_up
-                        .system()
-                        .after(BaseSystemLabels::CheckPlayerPosition),
-                ),
-        );
+        app.insert_resource(Base { active: false })
+            .add_event::<FuelBought>()
+            .add_event::<LampUpgraded>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(
+                        check_player_position
+                            .system()
+                            .label(BaseSystemLabels::CheckPlayerPosition),
+                    )
+                    .with_system(
+                        fuel_up
+                            .system()
+                            .label(BaseSystemLabels::FuelUp)
+                            .after(BaseSystemLabels::CheckPlayerPosition),
+                    )
+                    .with_system(
+                        upgrade_lamp
+                            .system()
+                            .after(BaseSystemLabels::FuelUp)
+                            .after(ActionsSystemLabels::SetActions),
+                    ),
+            );
     }
 }
 
@@ -33,6 +49,12 @@ pub struct Base {
     pub active: bool,
 }
 
+pub struct FuelBought {
+    pub amount: f32,
+}
+
+pub struct LampUpgraded;
+
 fn check_player_position(
     digger: Query<&Transform, With<Digger>>,
     map: Res<Map>,
@@ -45,15 +67,36 @@ fn check_player_position(
     }
 }
 
-fn fuel_up(base: Res<Base>, mut digger_state: ResMut<DiggerState>) {
+fn fuel_up(
+    base: Res<Base>,
+    mut digger_state: ResMut<DiggerState>,
+    mut fuel_bought: EventWriter<FuelBought>,
+) {
     if base.active {
-        let to_fuel = digger_state.fuel_max - digger_state.fuel;
-        if to_fuel > digger_state.money {
-            digger_state.fuel += digger_state.money;
-            digger_state.money = 0.;
-        } else {
+        let to_fuel = (digger_state.fuel_max - digger_state.fuel).min(digger_state.money);
+        if to_fuel > 0. {
+            digger_state.fuel += to_fuel;
             digger_state.money -= to_fuel;
-            digger_state.fuel = digger_state.fuel_max;
+            fuel_bought.send(FuelBought { amount: to_fuel });
         }
     }
 }
+
+/// Refueling comes first, the lamp can only be upgraded with the money left after that
+fn upgrade_lamp(
+    base: Res<Base>,
+    actions: Res<Actions>,
+    mut digger_state: ResMut<DiggerState>,
+    mut lamp_upgraded: EventWriter<LampUpgraded>,
+) {
+    if base.active
+        && actions.upgrade
+        && digger_state.money >= LAMP_UPGRADE_PRICE
+        && digger_state.light_radius < MAX_LIGHT_RADIUS
+    {
+        digger_state.money -= LAMP_UPGRADE_PRICE;
+        digger_state.light_radius =
+            (digger_state.light_radius + LAMP_UPGRADE_RADIUS).min(MAX_LIGHT_RADIUS);
+        lamp_upgraded.send(LampUpgraded);
+    }
+}
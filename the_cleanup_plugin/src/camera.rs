@@ -0,0 +1,221 @@
+use crate::actions::Actions;
+use crate::digger::{Digger, DiggerState, DiggerSystemLabels, TileMined};
+use crate::map::{Map, PlayerCamera};
+use crate::settings::Settings;
+use crate::GameState;
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+use bevy::render::camera::{Camera, CameraProjection, OrthographicProjection};
+use rand::random;
+
+/// How fast the camera catches up with its target; higher is stiffer
+const FOLLOW_SPEED: f32 = 6.;
+/// Distance in tiles the camera looks ahead in the direction the digger moves
+const LOOK_AHEAD: f32 = 2.5;
+/// How fast the look-ahead turns around when the digger changes direction
+const LOOK_AHEAD_SPEED: f32 = 2.;
+/// Offset in pixels at full shake
+const MAX_SHAKE_OFFSET: f32 = 12.;
+/// Shake lost per second
+const SHAKE_DECAY: f32 = 1.5;
+/// Shake when a tile breaks
+const TILE_MINED_SHAKE: f32 = 0.2;
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 2.;
+/// Zoom factor of one key press or one line of the mouse wheel
+const ZOOM_STEP: f32 = 1.25;
+/// Pixels of a touchpad scroll that count as one line of the mouse wheel
+const PIXELS_PER_LINE: f32 = 50.;
+/// How fast the zoom reaches its target
+const ZOOM_SPEED: f32 = 10.;
+
+pub struct CameraPlugin;
+
+#[derive(SystemLabel, Eq, PartialEq, Hash, Clone, Debug)]
+pub enum CameraSystemLabels {
+    Zoom,
+    MoveCamera,
+}
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_event::<CameraShake>()
+            .init_resource::<CameraZoom>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(reset_camera.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(shake_on_tile_mined.system())
+                    .with_system(zoom_camera.system().label(CameraSystemLabels::Zoom))
+                    .with_system(
+                        move_camera
+                            .system()
+                            .label(CameraSystemLabels::MoveCamera)
+                            .after(CameraSystemLabels::Zoom)
+                            .after(DiggerSystemLabels::MoveDigger),
+                    ),
+            );
+    }
+}
+
+/// Shakes the camera; a strength of `1.` is the strongest shake
+pub struct CameraShake {
+    pub strength: f32,
+}
+
+/// Zoom on top of the pixels per tile from the settings; kept between runs
+struct CameraZoom {
+    target: f32,
+    current: f32,
+}
+
+impl Default for CameraZoom {
+    fn default() -> Self {
+        CameraZoom {
+            target: 1.,
+            current: 1.,
+        }
+    }
+}
+
+/// Where the camera would be without shaking
+struct CameraFocus {
+    position: Vec2,
+    look_ahead: Vec2,
+    shake: f32,
+}
+
+fn reset_camera(mut commands: Commands, map: Res<Map>) {
+    commands.insert_resource(CameraFocus {
+        position: map.base,
+        look_ahead: Vec2::ZERO,
+        shake: 0.,
+    });
+}
+
+fn shake_on_tile_mined(
+    mut tile_mined: EventReader<TileMined>,
+    mut camera_shake: EventWriter<CameraShake>,
+) {
+    for _event in tile_mined.iter() {
+        camera_shake.send(CameraShake {
+            strength: TILE_MINED_SHAKE,
+        });
+    }
+}
+
+fn zoom_camera(
+    time: Res<Time>,
+    map: Res<Map>,
+    settings: Res<Settings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut mouse_wheel: EventReader<MouseWheel>,
+    mut zoom: ResMut<CameraZoom>,
+    mut camera_query: Query<(&mut Camera, &mut OrthographicProjection), With<PlayerCamera>>,
+) {
+    let mut steps = 0.;
+    if keyboard_input.just_pressed(KeyCode::Equals)
+        || keyboard_input.just_pressed(KeyCode::NumpadAdd)
+    {
+        steps += 1.;
+    }
+    if keyboard_input.just_pressed(KeyCode::Minus)
+        || keyboard_input.just_pressed(KeyCode::NumpadSubtract)
+    {
+        steps -= 1.;
+    }
+    for event in mouse_wheel.iter() {
+        steps += match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / PIXELS_PER_LINE,
+        };
+    }
+    if keyboard_input.just_pressed(KeyCode::Key0) {
+        zoom.target = 1.;
+    }
+    if steps != 0. {
+        zoom.target = (zoom.target * ZOOM_STEP.powf(steps)).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+    if (zoom.target - zoom.current).abs() < 0.001 {
+        zoom.current = zoom.target;
+    } else {
+        zoom.current = zoom.current
+            + (zoom.target - zoom.current) * (1. - (-ZOOM_SPEED * time.delta_seconds()).exp());
+    }
+
+    let scale = map.tile_size / (settings.pixels_per_tile * zoom.current);
+    for (mut camera, mut projection) in camera_query.iter_mut() {
+        if projection.scale != scale {
+            projection.scale = scale;
+            // the camera only updates its projection by itself when the window is resized
+            camera.projection_matrix = projection.get_projection_matrix();
+        }
+    }
+}
+
+/// Keeps `position` between `min` and `max`, or centered if the range is smaller than the view
+fn clamp_axis(position: f32, min: f32, max: f32, half_view: f32) -> f32 {
+    if max - min < 2. * half_view {
+        (min + max) / 2.
+    } else {
+        position.clamp(min + half_view, max - half_view)
+    }
+}
+
+fn move_camera(
+    time: Res<Time>,
+    map: Res<Map>,
+    actions: Res<Actions>,
+    digger_state: Res<DiggerState>,
+    windows: Res<Windows>,
+    mut focus: ResMut<CameraFocus>,
+    mut camera_shake: EventReader<CameraShake>,
+    digger_query: Query<&Transform, (With<Digger>, Without<PlayerCamera>)>,
+    mut camera_query: Query<(&mut Transform, &OrthographicProjection), With<PlayerCamera>>,
+) {
+    let digger = match digger_query.single() {
+        Ok(transform) => Vec2::new(transform.translation.x, transform.translation.y),
+        Err(_) => return,
+    };
+    let delta = time.delta_seconds();
+    for event in camera_shake.iter() {
+        focus.shake = (focus.shake + event.strength).min(1.);
+    }
+    focus.shake = (focus.shake - SHAKE_DECAY * delta).max(0.);
+
+    let vertical = if digger_state.falling_speed > 0. {
+        1.
+    } else if digger_state.falling_speed < 0. {
+        -1.
+    } else {
+        0.
+    };
+    let direction = Vec2::new(actions.player_movement.unwrap_or(0.), vertical);
+    focus.look_ahead = focus.look_ahead
+        + (direction * LOOK_AHEAD * map.tile_size - focus.look_ahead)
+            * (1. - (-LOOK_AHEAD_SPEED * delta).exp());
+
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    for (mut transform, projection) in camera_query.iter_mut() {
+        let half_view = Vec2::new(window.width(), window.height()) / 2. * projection.scale;
+        // tiles are centered on their position
+        let min = Vec2::splat(-map.tile_size / 2.);
+        let max = Vec2::new(map.width() as f32, map.height() as f32) * map.tile_size + min;
+        let target = digger + focus.look_ahead;
+        let target = Vec2::new(
+            clamp_axis(target.x, min.x, max.x, half_view.x),
+            clamp_axis(target.y, min.y, max.y, half_view.y),
+        );
+        focus.position =
+            focus.position + (target - focus.position) * (1. - (-FOLLOW_SPEED * delta).exp());
+
+        let shake = focus.shake * focus.shake * MAX_SHAKE_OFFSET;
+        let offset = Vec2::new(random::<f32>() * 2. - 1., random::<f32>() * 2. - 1.) * shake;
+        transform.translation.x = (focus.position.x + offset.x).round();
+        transform.translation.y = (focus.position.y + offset.y).round();
+    }
+}
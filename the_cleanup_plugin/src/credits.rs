@@ -0,0 +1,93 @@
+use crate::locale::Locale;
+use crate::theme::UiTheme;
+use crate::widgets::{
+    spawn_button, spawn_label, spawn_screen, ButtonActionPlugin, ButtonMaterials, LabelStyle,
+};
+use crate::GameState;
+use bevy::prelude::*;
+
+/// Locale keys of the credit lines; empty keys are blank lines
+const CREDITS: [&str; 7] = [
+    "credits-jam",
+    "credits-engine",
+    "",
+    "credits-kenney",
+    "credits-license",
+    "",
+    "credits-other",
+];
+
+pub struct CreditsPlugin;
+
+impl Plugin for CreditsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_plugin(ButtonActionPlugin::<CreditsAction>::default())
+            .add_system_set(
+                SystemSet::on_enter(GameState::Credits).with_system(setup_credits.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Credits).with_system(handle_actions.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Credits).with_system(remove_credits.system()),
+            );
+    }
+}
+
+struct CreditsUi;
+
+#[derive(Clone, Copy)]
+enum CreditsAction {
+    Back,
+}
+
+fn setup_credits(
+    mut commands: Commands,
+    theme: Res<UiTheme>,
+    locale: Res<Locale>,
+    button_materials: Res<ButtonMaterials>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands
+        .spawn_bundle(UiCameraBundle::default())
+        .insert(CreditsUi);
+    spawn_screen(
+        &mut commands,
+        &mut color_materials,
+        theme.palette.background,
+    )
+    .insert(CreditsUi)
+    .with_children(|parent| {
+        spawn_label(
+            parent,
+            &theme,
+            locale.get("credits-title"),
+            LabelStyle::Title,
+        );
+        for line in CREDITS.iter() {
+            spawn_label(parent, &theme, locale.get(line), LabelStyle::Body);
+        }
+        spawn_button(
+            parent,
+            &button_materials,
+            &theme,
+            locale.get("back"),
+            240.,
+            CreditsAction::Back,
+        );
+    });
+}
+
+fn handle_actions(mut actions: EventReader<CreditsAction>, mut state: ResMut<State<GameState>>) {
+    for action in actions.iter() {
+        match action {
+            CreditsAction::Back => state.set(GameState::Menu).unwrap(),
+        }
+    }
+}
+
+fn remove_credits(mut commands: Commands, query: Query<Entity, With<CreditsUi>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
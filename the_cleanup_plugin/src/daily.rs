@@ -0,0 +1,314 @@
+use crate::digger::DiggerState;
+use crate::locale::Locale;
+use crate::persistence;
+use crate::rules::{GameLost, GameWon, RulesSystemLabels, RunTimer};
+use crate::theme::UiTheme;
+use crate::widgets::{
+    spawn_button, spawn_label, spawn_screen, ButtonActionPlugin, ButtonMaterials, LabelStyle,
+};
+use crate::GameState;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const HISTORY_FILE: &str = "daily.ron";
+const SHOWN_DAYS: usize = 7;
+
+pub struct DailyPlugin;
+
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_plugin(ButtonActionPlugin::<DailyHistoryAction>::default())
+            .init_resource::<DailyChallenge>()
+            .insert_resource(persistence::load::<DailyHistory>(HISTORY_FILE))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(
+                    record_result
+                        .system()
+                        .after(RulesSystemLabels::EvaluateRules),
+                ),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::DailyHistory).with_system(setup_history.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::DailyHistory).with_system(handle_actions.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::DailyHistory).with_system(remove_history.system()),
+            );
+    }
+}
+
+/// Set while the current run is the daily challenge
+#[derive(Default)]
+pub struct DailyChallenge {
+    pub active: bool,
+    pub day: u64,
+}
+
+impl DailyChallenge {
+    /// Days since the unix epoch in UTC
+    pub fn today() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs() / 86_400)
+            .unwrap_or(0)
+    }
+
+    /// Map seed of the given day; only depends on the date, so every player gets the same map
+    pub fn seed(day: u64) -> u64 {
+        (day ^ 0x7468_655f_636c_6561).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DailyResult {
+    pub day: u64,
+    pub won: bool,
+    pub waste: usize,
+    pub time: f32,
+    pub money: f32,
+}
+
+impl DailyResult {
+    /// A won run beats a lost one. Won runs are compared by time, lost ones by collected waste.
+    fn is_better_than(&self, other: &DailyResult) -> bool {
+        match (self.won, other.won) {
+            (true, false) => true,
+            (false, true) => false,
+            (true, true) => self.time < other.time,
+            (false, false) => self.waste > other.waste,
+        }
+    }
+
+    fn describe(&self, locale: &Locale) -> String {
+        if self.won {
+            locale.format(
+                "daily-won",
+                &[
+                    ("time", locale.number(self.time, 1)),
+                    ("money", locale.money(self.money)),
+                ],
+            )
+        } else {
+            locale.format("daily-lost", &[("waste", self.waste.to_string())])
+        }
+    }
+}
+
+/// Best result of every day a daily challenge was played
+#[derive(Serialize, Deserialize, Default)]
+pub struct DailyHistory {
+    pub results: Vec<DailyResult>,
+}
+
+impl DailyHistory {
+    fn submit(&mut self, result: DailyResult) -> bool {
+        match self
+            .results
+            .iter_mut()
+            .find(|existing| existing.day == result.day)
+        {
+            Some(existing) if result.is_better_than(existing) => *existing = result,
+            Some(_) => return false,
+            None => self.results.push(result),
+        }
+        self.results.sort_by(|a, b| b.day.cmp(&a.day));
+        true
+    }
+}
+
+fn record_result(
+    daily: Res<DailyChallenge>,
+    mut history: ResMut<DailyHistory>,
+    mut game_won: EventReader<GameWon>,
+    mut game_lost: EventReader<GameLost>,
+    digger_state: Res<DiggerState>,
+    run_timer: Res<RunTimer>,
+) {
+    let won = game_won.iter().count() > 0;
+    let lost = game_lost.iter().count() > 0;
+    if !daily.active || !(won || lost) {
+        return;
+    }
+    let result = DailyResult {
+        day: daily.day,
+        won,
+        waste: digger_state.waste,
+        time: run_timer.elapsed,
+        money: digger_state.money_earned,
+    };
+    if history.submit(result) {
+        persistence::save(HISTORY_FILE, &*history);
+    }
+}
+
+/// Civil date of the given number of days since the unix epoch
+fn format_day(day: u64) -> String {
+    // Howard Hinnant's days-to-civil algorithm
+    let z = day as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day_of_month = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{}-{:02}-{:02}", year, month, day_of_month)
+}
+
+struct DailyHistoryUi;
+
+#[derive(Clone, Copy)]
+enum DailyHistoryAction {
+    Back,
+}
+
+fn setup_history(
+    mut commands: Commands,
+    theme: Res<UiTheme>,
+    locale: Res<Locale>,
+    history: Res<DailyHistory>,
+    button_materials: Res<ButtonMaterials>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let today = DailyChallenge::today();
+    let mut lines: Vec<String> = history
+        .results
+        .iter()
+        .take(SHOWN_DAYS)
+        .map(|result| {
+            locale.format(
+                if result.day == today {
+                    "daily-entry-today"
+                } else {
+                    "daily-entry"
+                },
+                &[
+                    ("date", format_day(result.day)),
+                    ("result", result.describe(&locale)),
+                ],
+            )
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push(locale.get("daily-empty"));
+    }
+    commands
+        .spawn_bundle(UiCameraBundle::default())
+        .insert(DailyHistoryUi);
+    spawn_screen(
+        &mut commands,
+        &mut color_materials,
+        theme.palette.background,
+    )
+    .insert(DailyHistoryUi)
+    .with_children(|parent| {
+        spawn_label(parent, &theme, locale.get("daily-title"), LabelStyle::Title);
+        for line in lines {
+            spawn_label(parent, &theme, line, LabelStyle::Body);
+        }
+        spawn_button(
+            parent,
+            &button_materials,
+            &theme,
+            locale.get("back"),
+            240.,
+            DailyHistoryAction::Back,
+        );
+    });
+}
+
+fn handle_actions(
+    mut actions: EventReader<DailyHistoryAction>,
+    mut state: ResMut<State<GameState>>,
+) {
+    for action in actions.iter() {
+        match action {
+            DailyHistoryAction::Back => state.set(GameState::NewGame).unwrap(),
+        }
+    }
+}
+
+fn remove_history(mut commands: Commands, query: Query<Entity, With<DailyHistoryUi>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(day: u64, won: bool, waste: usize, time: f32) -> DailyResult {
+        DailyResult {
+            day,
+            won,
+            waste,
+            time,
+            money: 0.,
+        }
+    }
+
+    #[test]
+    fn won_beats_lost() {
+        let won = result(1, true, 10, 300.);
+        let lost = result(1, false, 9, 100.);
+        assert!(won.is_better_than(&lost));
+        assert!(!lost.is_better_than(&won));
+    }
+
+    #[test]
+    fn faster_won_run_is_better() {
+        let fast = result(1, true, 10, 100.);
+        let slow = result(1, true, 10, 200.);
+        assert!(fast.is_better_than(&slow));
+        assert!(!slow.is_better_than(&fast));
+        assert!(!fast.is_better_than(&fast));
+    }
+
+    #[test]
+    fn lost_run_with_more_waste_is_better() {
+        let more = result(1, false, 5, 200.);
+        let less = result(1, false, 3, 100.);
+        assert!(more.is_better_than(&less));
+        assert!(!less.is_better_than(&more));
+        assert!(!more.is_better_than(&more));
+    }
+
+    #[test]
+    fn submit_keeps_the_best_result_per_day() {
+        let mut history = DailyHistory::default();
+        assert!(history.submit(result(1, false, 3, 100.)));
+        assert!(history.submit(result(1, true, 10, 200.)));
+        assert!(!history.submit(result(1, true, 10, 300.)));
+        assert_eq!(history.results.len(), 1);
+        assert!(history.results[0].won);
+        assert_eq!(history.results[0].time, 200.);
+    }
+
+    #[test]
+    fn submit_sorts_newest_day_first() {
+        let mut history = DailyHistory::default();
+        history.submit(result(1, false, 3, 100.));
+        history.submit(result(3, false, 3, 100.));
+        history.submit(result(2, false, 3, 100.));
+        let days: Vec<u64> = history.results.iter().map(|result| result.day).collect();
+        assert_eq!(days, vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn formats_days_as_dates() {
+        assert_eq!(format_day(0), "1970-01-01");
+        assert_eq!(format_day(11_016), "2000-02-29");
+        assert_eq!(format_day(18_321), "2020-02-29");
+        assert_eq!(format_day(18_322), "2020-03-01");
+    }
+}
@@ -0,0 +1,17 @@
+use bevy::prelude::*;
+
+/// Helpers for working on the game; only built with the `dev` feature
+pub struct DevPlugin;
+
+impl Plugin for DevPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system(watch_assets.system());
+    }
+}
+
+/// Reloads assets as soon as they change in the `assets` directory
+fn watch_assets(asset_server: Res<AssetServer>) {
+    if let Err(error) = asset_server.watch_for_changes() {
+        warn!("Failed to watch the assets for changes: {}", error);
+    }
+}
@@ -1,6 +1,8 @@
 use crate::actions::Actions;
-use crate::loading::TextureAssets;
-use crate::map::{Map, MapSystemLabels, MapTile, MiningEffect, PlayerCamera, Tile};
+use crate::camera::CameraShake;
+use crate::loading::SpriteAtlas;
+use crate::map::{Map, MapSystemLabels, MapTile, MiningEffect, Tile};
+use crate::rules::GameRules;
 use crate::GameState;
 use bevy::prelude::*;
 
@@ -9,11 +11,21 @@ pub struct DiggerPlugin;
 const Y_OFFSET_TO_DIGGER_BOTTOM: f32 = 10.;
 const LEFT_OFFSET_TO_DIGGER_BORDER: f32 = 11.;
 const RIGHT_OFFSET_TO_DIGGER_BORDER: f32 = 12.;
+/// Horizontal speed in pixels per second
+pub const DRIVING_SPEED: f32 = 200.;
+/// Gain in upwards speed per second while flying; also the top climbing speed
+pub const FLYING_RATE: f32 = 300.;
+/// Gain in downwards speed per second while falling; also the top falling speed
+const FALLING_RATE: f32 = 500.;
+/// Landing faster than this shakes the camera
+const IMPACT_SPEED: f32 = 250.;
 
 #[derive(SystemLabel, Eq, PartialEq, Hash, Clone, Debug)]
 pub enum DiggerSystemLabels {
     MoveDigger,
     MarkMiningTarget,
+    Dig,
+    LooseFuel,
 }
 
 impl Plugin for DiggerPlugin {
@@ -21,6 +33,7 @@ impl Plugin for DiggerPlugin {
         app.init_resource::<DiggerState>()
             .add_event::<FuelUpgrade>()
             .add_event::<WasteCollected>()
+            .add_event::<TileMined>()
             .add_system_set(
                 SystemSet::on_enter(GameState::Playing).with_system(spawn_digger.system()),
             )
@@ -33,16 +46,23 @@ impl Plugin for DiggerPlugin {
                             .label(DiggerSystemLabels::MarkMiningTarget)
                             .after(DiggerSystemLabels::MoveDigger),
                     )
-                    .with_system(loose_fuel.system())
+                    .with_system(track_depth.system().after(DiggerSystemLabels::MoveDigger))
+                    .with_system(loose_fuel.system().label(DiggerSystemLabels::LooseFuel))
                     .with_system(update_fall_and_fly.system())
-                    .with_system(dig.system().after(DiggerSystemLabels::MarkMiningTarget)),
+                    .with_system(
+                        dig.system()
+                            .label(DiggerSystemLabels::Dig)
+                            .after(DiggerSystemLabels::MarkMiningTarget),
+                    ),
             )
             .add_system_set(
-                SystemSet::on_exit(GameState::Playing).with_system(
-                    despawn_digger
-                        .system()
-                        .before(MapSystemLabels::DespawnMapAndCamera),
-                ),
+                SystemSet::on_exit(GameState::Playing)
+                    .with_system(
+                        despawn_digger
+                            .system()
+                            .before(MapSystemLabels::DespawnMapAndCamera),
+                    )
+                    .with_system(reset_digger_state.system()),
             );
     }
 }
@@ -51,19 +71,31 @@ pub struct FuelUpgrade;
 
 pub struct WasteCollected;
 
+pub struct TileMined {
+    pub tile: Tile,
+    pub x: usize,
+    pub y: usize,
+}
+
 pub struct Digger;
 
 pub struct DiggerState {
     pub waste: usize,
     pub dead: bool,
     pub money: f32,
+    pub money_earned: f32,
+    pub depth: usize,
+    pub max_depth: usize,
     pub fuel: f32,
     pub fuel_max: f32,
+    pub fuel_consumed: f32,
     pub mining_strength: f32,
     pub mining_target: Option<(usize, usize)>,
     pub mining: f32,
     pub falling: bool,
     pub falling_speed: f32,
+    /// Tiles closer to the digger than this are lit
+    pub light_radius: f32,
 }
 
 impl Default for DiggerState {
@@ -73,25 +105,26 @@ impl Default for DiggerState {
             mining_target: None,
             dead: false,
             money: 0.,
+            money_earned: 0.,
+            depth: 0,
+            max_depth: 0,
             mining_strength: 10.,
             mining: 0.,
             fuel: 20.,
             fuel_max: 20.,
+            fuel_consumed: 0.,
             falling: false,
             falling_speed: 0.,
+            light_radius: 4.,
         }
     }
 }
 
-fn spawn_digger(
-    mut commands: Commands,
-    map: Res<Map>,
-    texture_assets: Res<TextureAssets>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
+fn spawn_digger(mut commands: Commands, map: Res<Map>, sprite_atlas: Res<SpriteAtlas>) {
     commands
-        .spawn_bundle(SpriteBundle {
-            material: materials.add(texture_assets.texture_digger.clone().into()),
+        .spawn_bundle(SpriteSheetBundle {
+            texture_atlas: sprite_atlas.atlas.clone(),
+            sprite: TextureAtlasSprite::new(sprite_atlas.index("digger")),
             transform: Transform::from_translation(Vec3::new(
                 map.base.x,
                 map.base.y + map.tile_size,
@@ -107,8 +140,8 @@ fn move_digger(
     mut digger_state: ResMut<DiggerState>,
     actions: Res<Actions>,
     map: Res<Map>,
-    mut camera_query: Query<&mut Transform, (With<PlayerCamera>, Without<Digger>)>,
-    mut digger_query: Query<&mut Transform, (With<Digger>, Without<PlayerCamera>)>,
+    mut camera_shake: EventWriter<CameraShake>,
+    mut digger_query: Query<&mut Transform, With<Digger>>,
 ) {
     if digger_state.dead {
         return;
@@ -129,8 +162,7 @@ fn move_digger(
     let mut x = 0.;
     let mut y = 0.;
     if let Some(drive) = actions.player_movement {
-        let speed = 200.;
-        x += drive * speed * time.delta_seconds();
+        x += drive * DRIVING_SPEED * time.delta_seconds();
     }
     y += digger_state.falling_speed * time.delta_seconds();
     for mut digger_transform in digger_query.iter_mut() {
@@ -162,6 +194,11 @@ fn move_digger(
             let next_tile_left = &map.tiles[slot_next_y][slot_x_left];
             let next_tile_right = &map.tiles[slot_next_y][slot_x_right];
             if next_tile_left.collides() || next_tile_right.collides() {
+                if digger_state.falling_speed.abs() > IMPACT_SPEED {
+                    camera_shake.send(CameraShake {
+                        strength: (digger_state.falling_speed.abs() - IMPACT_SPEED) / IMPACT_SPEED,
+                    });
+                }
                 digger_state.falling_speed = 0.;
                 y = if y > 0. {
                     y + (map.tile_size / 2.)
@@ -198,18 +235,32 @@ fn move_digger(
 
         digger_transform.translation.y += y;
         digger_transform.translation.x += x;
-        for mut transform in camera_query.iter_mut() {
-            transform.translation = digger_transform.translation;
+    }
+}
+
+fn track_depth(
+    mut digger_state: ResMut<DiggerState>,
+    map: Res<Map>,
+    digger_query: Query<&Transform, With<Digger>>,
+) {
+    if let Ok(transform) = digger_query.single() {
+        let depth = map.depth_at(transform.translation.y);
+        if digger_state.depth != depth {
+            digger_state.depth = depth;
+        }
+        if depth > digger_state.max_depth {
+            digger_state.max_depth = depth;
         }
     }
 }
 
-fn loose_fuel(mut digger_state: ResMut<DiggerState>, time: Res<Time>) {
+fn loose_fuel(mut digger_state: ResMut<DiggerState>, rules: Res<GameRules>, time: Res<Time>) {
     if digger_state.dead {
         return;
     }
-    let fuel_rate = 0.5;
-    digger_state.fuel -= fuel_rate * time.delta_seconds();
+    let consumed = (rules.fuel_rate * time.delta_seconds()).min(digger_state.fuel);
+    digger_state.fuel -= consumed;
+    digger_state.fuel_consumed += consumed;
     digger_state.fuel = digger_state.fuel.clamp(0., digger_state.fuel_max);
 }
 
@@ -220,8 +271,6 @@ fn update_fall_and_fly(
     map: Res<Map>,
     digger_query: Query<&Transform, With<Digger>>,
 ) {
-    let falling_rate = 500.;
-    let flying_rate = 300.;
     for digger_transform in digger_query.iter() {
         let new_border_translation_y =
             digger_transform.translation.y - Y_OFFSET_TO_DIGGER_BOTTOM - 1.;
@@ -237,13 +286,13 @@ fn update_fall_and_fly(
         digger_state.falling = !next_tile_left.collides() && !next_tile_right.collides();
     }
     if actions.flying {
-        digger_state.falling_speed += flying_rate * time.delta_seconds();
+        digger_state.falling_speed += FLYING_RATE * time.delta_seconds();
     } else if digger_state.falling {
-        digger_state.falling_speed -= falling_rate * time.delta_seconds();
+        digger_state.falling_speed -= FALLING_RATE * time.delta_seconds();
     } else {
         digger_state.falling_speed = 0.;
     }
-    digger_state.falling_speed = digger_state.falling_speed.clamp(-falling_rate, flying_rate);
+    digger_state.falling_speed = digger_state.falling_speed.clamp(-FALLING_RATE, FLYING_RATE);
 }
 
 fn dig(
@@ -252,9 +301,9 @@ fn dig(
     mut map: ResMut<Map>,
     mut fuel_upgrade: EventWriter<FuelUpgrade>,
     mut waste_collected: EventWriter<WasteCollected>,
-    mut tile_query: Query<(Entity, &MapTile, &mut Handle<ColorMaterial>), With<Mining>>,
-    texture_assets: Res<TextureAssets>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut tile_mined: EventWriter<TileMined>,
+    mut tile_query: Query<(Entity, &MapTile, &mut TextureAtlasSprite), With<Mining>>,
+    sprite_atlas: Res<SpriteAtlas>,
 ) {
     if digger_state.mining_target.is_none() {
         return;
@@ -264,6 +313,7 @@ fn dig(
     if digger_state.mining >= tile.mining_strength().unwrap() {
         if let Some(MiningEffect::Money(value)) = tile.effect() {
             digger_state.money += value;
+            digger_state.money_earned += value;
         } else if let Some(MiningEffect::TankUpgrade(value)) = tile.effect() {
             fuel_upgrade.send(FuelUpgrade);
             digger_state.fuel += value;
@@ -272,14 +322,19 @@ fn dig(
             waste_collected.send(WasteCollected);
             digger_state.waste += 1;
         }
-        for (entity, map_tile, mut material) in tile_query.iter_mut() {
+        for (entity, map_tile, mut sprite) in tile_query.iter_mut() {
             if map_tile.x != digger_state.mining_target.unwrap().0
                 || map_tile.y != digger_state.mining_target.unwrap().1
             {
                 continue;
             }
             commands.entity(entity).insert(Mined);
-            *material = materials.add(texture_assets.texture_background.clone().into());
+            tile_mined.send(TileMined {
+                tile: tile.clone(),
+                x: map_tile.x,
+                y: map_tile.y,
+            });
+            sprite.index = sprite_atlas.tile_index(&Tile::Background);
             map.tiles[digger_state.mining_target.unwrap().1]
                 [digger_state.mining_target.unwrap().0] = Tile::Background;
             digger_state.mining_target = None;
@@ -294,24 +349,23 @@ struct Mined;
 
 fn mark_mining_target(
     mut commands: Commands,
-    mut tile_query: Query<(Entity, &MapTile, &mut Handle<ColorMaterial>), Without<Mining>>,
+    mut tile_query: Query<(Entity, &MapTile, &mut TextureAtlasSprite), Without<Mining>>,
     mut mining_tile_query: Query<
-        (Entity, &MapTile, &mut Handle<ColorMaterial>),
+        (Entity, &MapTile, &mut TextureAtlasSprite),
         (With<Mining>, Without<Mined>),
     >,
     digger_state: Res<DiggerState>,
-    texture_assets: Res<TextureAssets>,
+    sprite_atlas: Res<SpriteAtlas>,
     map: Res<Map>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
     if digger_state.mining_target.is_none() {
-        for (entity, map_tile, mut material) in mining_tile_query.iter_mut() {
+        for (entity, map_tile, mut sprite) in mining_tile_query.iter_mut() {
             let tile = &map.tiles[map_tile.y][map_tile.x];
-            *material = materials.add(texture_assets.get_tile_handle(tile).into());
+            sprite.index = sprite_atlas.tile_index(tile);
             commands.entity(entity).remove::<Mining>();
         }
     } else {
-        for (entity, map_tile, mut material) in tile_query.iter_mut() {
+        for (entity, map_tile, mut sprite) in tile_query.iter_mut() {
             if map_tile.x != digger_state.mining_target.unwrap().0
                 || map_tile.y != digger_state.mining_target.unwrap().1
             {
@@ -319,13 +373,17 @@ fn mark_mining_target(
             }
             commands.entity(entity).insert(Mining);
             let tile = &map.tiles[map_tile.y][map_tile.x];
-            if let Some(handle) = texture_assets.get_mining_tile_handle(tile) {
-                *material = materials.add(handle.into());
+            if let Some(index) = sprite_atlas.mining_tile_index(tile) {
+                sprite.index = index;
             }
         }
     }
 }
 
+fn reset_digger_state(mut digger_state: ResMut<DiggerState>) {
+    *digger_state = DiggerState::default();
+}
+
 fn despawn_digger(mut commands: Commands, digger: Query<Entity, With<Digger>>) {
     for digger in digger.iter() {
         commands.entity(digger).despawn();
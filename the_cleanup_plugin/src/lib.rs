@@ -1,31 +1,81 @@
 mod actions;
 mod audio;
 mod base;
+mod camera;
+mod credits;
+mod daily;
+#[cfg(feature = "dev")]
+mod dev;
 mod digger;
+mod lighting;
 mod loading;
+mod locale;
 mod map;
 mod menu;
+mod minimap;
+mod music;
+mod navigation;
+mod new_game;
+mod pause;
+mod persistence;
+mod results;
+mod rules;
+mod settings;
+mod statistics;
+mod theme;
 mod ui;
+mod visibility;
+mod warnings;
+mod widgets;
 
 use crate::actions::ActionsPlugin;
 use crate::audio::InternalAudioPlugin;
 use crate::base::BasePlugin;
+use crate::camera::CameraPlugin;
+use crate::credits::CreditsPlugin;
+use crate::daily::DailyPlugin;
+#[cfg(feature = "dev")]
+use crate::dev::DevPlugin;
 use crate::digger::DiggerPlugin;
+use crate::lighting::LightingPlugin;
 use crate::loading::LoadingPlugin;
+use crate::locale::LocalePlugin;
 use crate::map::MapPlugin;
 use crate::menu::MenuPlugin;
+use crate::minimap::MinimapPlugin;
+use crate::music::MusicPlugin;
+use crate::navigation::NavigationPlugin;
+use crate::new_game::NewGamePlugin;
+use crate::pause::PausePlugin;
+use crate::results::ResultsPlugin;
+use crate::rules::RulesPlugin;
+use crate::settings::SettingsPlugin;
+use crate::statistics::StatisticsPlugin;
+use crate::theme::ThemePlugin;
 use crate::ui::UiPlugin;
+use crate::visibility::VisibilityPlugin;
+use crate::warnings::WarningsPlugin;
+use crate::widgets::WidgetsPlugin;
 use bevy::app::AppBuilder;
 // use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
 use bevy::ecs::schedule::SystemSet;
 use bevy::prelude::*;
 
+pub use crate::settings::Settings;
+
 #[derive(Clone, Eq, PartialEq, Debug, Hash)]
 enum GameState {
     Restart,
     Loading,
     Playing,
     Menu,
+    NewGame,
+    Credits,
+    DailyHistory,
+    Statistics,
+    Results,
+    Paused,
+    Settings,
 }
 
 pub struct GamePlugin;
@@ -33,19 +83,40 @@ pub struct GamePlugin;
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_state(GameState::Loading)
+            .add_plugin(SettingsPlugin)
             .add_plugin(LoadingPlugin)
             .add_plugin(UiPlugin)
+            .add_plugin(MinimapPlugin)
+            .add_plugin(LightingPlugin)
+            .add_plugin(VisibilityPlugin)
             .add_plugin(MapPlugin)
+            .add_plugin(CameraPlugin)
             .add_plugin(MenuPlugin)
+            .add_plugin(NewGamePlugin)
+            .add_plugin(CreditsPlugin)
+            .add_plugin(NavigationPlugin)
+            .add_plugin(WidgetsPlugin)
+            .add_plugin(ThemePlugin)
+            .add_plugin(LocalePlugin)
+            .add_plugin(DailyPlugin)
             .add_plugin(DiggerPlugin)
+            .add_plugin(RulesPlugin)
+            .add_plugin(WarningsPlugin)
+            .add_plugin(StatisticsPlugin)
+            .add_plugin(ResultsPlugin)
+            .add_plugin(PausePlugin)
             .add_plugin(ActionsPlugin)
             .add_plugin(BasePlugin)
             .add_plugin(InternalAudioPlugin)
+            .add_plugin(MusicPlugin)
             // .add_plugin(FrameTimeDiagnosticsPlugin::default())
             // .add_plugin(LogDiagnosticsPlugin::default())
             .add_system_set(
                 SystemSet::on_enter(GameState::Restart).with_system(switch_to_game.system()),
             );
+
+        #[cfg(feature = "dev")]
+        app.add_plugin(DevPlugin);
     }
 }
 
@@ -0,0 +1,179 @@
+use crate::actions::{Actions, ActionsSystemLabels};
+use crate::digger::{Digger, DiggerState, DiggerSystemLabels};
+use crate::map::{Map, Tile};
+use crate::GameState;
+use bevy::prelude::*;
+
+/// Tiles closer to the digger than this are lit in every direction
+const HALO_RADIUS: f32 = 1.5;
+/// Half of the opening angle of the headlamp cone in radians
+const CONE_HALF_ANGLE: f32 = 0.6;
+/// Angle in radians over which the edge of the cone fades out
+const CONE_EDGE: f32 = 0.3;
+/// Width in tiles of the fade between lit and dark tiles at the end of the light
+const LIGHT_FALLOFF: f32 = 1.5;
+/// Ambient light right below the surface
+const AMBIENT_LIGHT: f32 = 0.6;
+/// Depth in meters at which the ambient light is gone
+const AMBIENT_DEPTH: f32 = 25.;
+/// Tiles closer to a glowing tile than this catch some of its glow
+const GLOW_RADIUS: f32 = 1.5;
+
+pub struct LightingPlugin;
+
+#[derive(SystemLabel, Eq, PartialEq, Hash, Clone, Debug)]
+pub enum LightingSystemLabels {
+    UpdateLightMap,
+}
+
+impl Plugin for LightingPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system_set(
+            SystemSet::on_enter(GameState::Playing).with_system(setup_light_map.system()),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Playing).with_system(
+                update_light_map
+                    .system()
+                    .label(LightingSystemLabels::UpdateLightMap)
+                    .after(DiggerSystemLabels::MoveDigger)
+                    .after(DiggerSystemLabels::Dig)
+                    .after(ActionsSystemLabels::SetActions),
+            ),
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::Playing).with_system(remove_light_map.system()),
+        );
+    }
+}
+
+/// Light of every map tile, calculated on the CPU each frame
+pub struct LightMap {
+    width: usize,
+    /// White light of the headlamp and the ambient light
+    lamp: Vec<f32>,
+    /// Colored light of glowing tiles; only changes when tiles are mined
+    glow: Vec<Vec3>,
+    /// Direction the headlamp points to; `1.` is right and `-1.` is left
+    facing: f32,
+}
+
+impl LightMap {
+    fn new(map: &Map) -> Self {
+        let mut light_map = LightMap {
+            width: map.width(),
+            lamp: vec![0.; map.width() * map.height()],
+            glow: vec![Vec3::ZERO; map.width() * map.height()],
+            facing: 1.,
+        };
+        light_map.update_glow(map);
+        light_map
+    }
+
+    /// Light of the headlamp and the ambient light at the tile
+    pub fn lamp(&self, x: usize, y: usize) -> f32 {
+        self.lamp[y * self.width + x]
+    }
+
+    /// All light at the tile as color
+    pub fn color(&self, x: usize, y: usize) -> Color {
+        let light = (Vec3::splat(self.lamp(x, y)) + self.glow[y * self.width + x]).min(Vec3::ONE);
+        Color::rgb(light.x, light.y, light.z)
+    }
+
+    fn update_glow(&mut self, map: &Map) {
+        let reach = GLOW_RADIUS.ceil() as isize;
+        self.glow.iter_mut().for_each(|glow| *glow = Vec3::ZERO);
+        for y in 0..map.height() {
+            for x in 0..map.width() {
+                let color = match glow_color(&map.tiles[y][x]) {
+                    Some(color) => color,
+                    None => continue,
+                };
+                for neighbour_y in (y as isize - reach).max(0)
+                    ..=(y as isize + reach).min(map.height() as isize - 1)
+                {
+                    for neighbour_x in (x as isize - reach).max(0)
+                        ..=(x as isize + reach).min(map.width() as isize - 1)
+                    {
+                        let distance = Vec2::new(neighbour_x as f32, neighbour_y as f32)
+                            .distance(Vec2::new(x as f32, y as f32));
+                        let strength = (1. - distance / GLOW_RADIUS).max(0.);
+                        self.glow[neighbour_y as usize * self.width + neighbour_x as usize] +=
+                            color * strength;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Ore and waste glow faintly in the dark
+fn glow_color(tile: &Tile) -> Option<Vec3> {
+    match tile {
+        Tile::Silver => Some(Vec3::new(0.25, 0.25, 0.3)),
+        Tile::Gold => Some(Vec3::new(0.4, 0.3, 0.05)),
+        Tile::Diamond => Some(Vec3::new(0.15, 0.4, 0.45)),
+        Tile::TankUpgrade => Some(Vec3::new(0.1, 0.2, 0.45)),
+        Tile::Waste => Some(Vec3::new(0.15, 0.5, 0.1)),
+        _ => None,
+    }
+}
+
+/// Light of the headlamp at a tile, given as offset to the digger in tiles
+fn headlamp(offset: Vec2, facing: f32, radius: f32) -> f32 {
+    let distance = offset.length();
+    let halo = ((HALO_RADIUS - distance) / LIGHT_FALLOFF + 1.).clamp(0., 1.);
+    if distance == 0. {
+        return halo;
+    }
+    let angle = (offset.x * facing / distance).clamp(-1., 1.).acos();
+    let cone = ((radius - distance) / LIGHT_FALLOFF + 1.).clamp(0., 1.)
+        * ((CONE_HALF_ANGLE - angle) / CONE_EDGE + 1.).clamp(0., 1.);
+    halo.max(cone)
+}
+
+fn ambient(depth: usize) -> f32 {
+    AMBIENT_LIGHT * (1. - depth as f32 / AMBIENT_DEPTH).max(0.)
+}
+
+fn setup_light_map(mut commands: Commands, map: Res<Map>) {
+    commands.insert_resource(LightMap::new(&map));
+}
+
+fn update_light_map(
+    map: Res<Map>,
+    actions: Res<Actions>,
+    digger_state: Res<DiggerState>,
+    mut light_map: ResMut<LightMap>,
+    digger_query: Query<&Transform, With<Digger>>,
+) {
+    if map.is_changed() {
+        light_map.update_glow(&map);
+    }
+    match actions.player_movement {
+        Some(movement) if movement > 0. => light_map.facing = 1.,
+        Some(movement) if movement < 0. => light_map.facing = -1.,
+        _ => (),
+    }
+    let digger = match digger_query.single() {
+        Ok(transform) => {
+            Vec2::new(transform.translation.x, transform.translation.y) / map.tile_size
+        }
+        Err(_) => return,
+    };
+    let facing = light_map.facing;
+    let width = light_map.width;
+    for y in 0..map.height() {
+        let ambient = ambient(map.depth_at(y as f32 * map.tile_size));
+        for x in 0..map.width() {
+            let offset = Vec2::new(x as f32, y as f32) - digger;
+            light_map.lamp[y * width + x] =
+                (ambient + headlamp(offset, facing, digger_state.light_radius)).min(1.);
+        }
+    }
+}
+
+fn remove_light_map(mut commands: Commands) {
+    commands.remove_resource::<LightMap>();
+}
@@ -1,171 +1,536 @@
-mod paths;
+mod manifest;
 
-use crate::loading::paths::PATHS;
+use crate::loading::manifest::{
+    AssetCollection, AssetManifest, AssetManifestLoader, MANIFEST_PATH,
+};
+use crate::locale::Locale;
 use crate::map::Tile;
+use crate::theme::UiTheme;
+use crate::widgets::spawn_screen;
 use crate::GameState;
+use bevy::app::AppExit;
 use bevy::asset::LoadState;
 use bevy::prelude::*;
+use bevy::sprite::{TextureAtlasBuilder, TextureAtlasBuilderError};
 use bevy_kira_audio::AudioSource;
+use std::collections::HashMap;
+use std::fmt;
 
 pub struct LoadingPlugin;
 
 impl Plugin for LoadingPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_system_set(
-            SystemSet::on_enter(GameState::Loading).with_system(start_loading.system()),
-        )
-        .add_system_set(SystemSet::on_update(GameState::Loading).with_system(check_state.system()))
-        .add_system_set(
-            SystemSet::on_exit(GameState::Loading).with_system(clean_up_loading.system()),
-        );
+        app.add_asset::<AssetManifest>()
+            .init_asset_loader::<AssetManifestLoader>()
+            .add_system(repack_textures.system())
+            .add_system_set(
+                SystemSet::on_enter(GameState::Loading).with_system(start_loading.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Loading)
+                    .with_system(check_state.system())
+                    .with_system(show_progress.system())
+                    .with_system(retry_or_quit.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Loading).with_system(clean_up_loading.system()),
+            );
     }
 }
 
+// Themes and translations are still loading, so the loading screen has its own colors and texts
+const BACKGROUND_COLOR: Color = Color::BLACK;
+const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+const BAR_COLOR: Color = Color::rgb(0.2, 0.7, 0.3);
+const BAR_BACKGROUND_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+const ERROR_COLOR: Color = Color::rgb(0.9, 0.15, 0.1);
+const FONT_SIZE: f32 = 25.;
+const RETRY_KEY: KeyCode = KeyCode::R;
+const QUIT_KEY: KeyCode = KeyCode::Escape;
+/// Built into the game, so failures to load the manifest or its assets can still be read
+const LOADING_FONT: &[u8] = include_bytes!("../../assets/fonts/FiraSans-Bold.ttf");
+
 struct LoadingIndicator;
 
+/// Column the progress bars of the asset groups are added to
+struct ProgressBars;
+
+/// Filled part of the progress bar of the asset group with this index
+struct ProgressBar(usize);
+
+/// Lists the assets that failed to load
+struct LoadingErrors;
+
 pub struct LoadingState {
-    textures: Vec<HandleUntyped>,
-    fonts: Vec<HandleUntyped>,
-    audio: Vec<HandleUntyped>,
+    manifest: Handle<AssetManifest>,
+    font: Handle<Font>,
+    /// One group per category of the manifest; empty until the manifest itself is loaded
+    groups: Vec<AssetGroup>,
+    /// Set when the loaded textures could not be packed into the sprite atlas
+    packing_error: Option<PackingError>,
 }
 
-pub struct FontAssets {
-    pub fira_sans: Handle<Font>,
+struct AssetGroup {
+    name: &'static str,
+    paths: Vec<String>,
+    handles: Vec<HandleUntyped>,
 }
 
-pub struct AudioAssets {
-    pub digging: Handle<AudioSource>,
-    pub flying: Handle<AudioSource>,
-    pub waste: Handle<AudioSource>,
-    pub fuel: Handle<AudioSource>,
+impl AssetGroup {
+    fn loaded(&self, asset_server: &AssetServer) -> usize {
+        self.handles
+            .iter()
+            .filter(|handle| asset_server.get_load_state(handle.id) == LoadState::Loaded)
+            .count()
+    }
+
+    fn failed(&self, asset_server: &AssetServer) -> Vec<usize> {
+        self.handles
+            .iter()
+            .enumerate()
+            .filter(|(_, handle)| asset_server.get_load_state(handle.id) == LoadState::Failed)
+            .map(|(index, _)| index)
+            .collect()
+    }
 }
 
-pub struct TextureAssets {
-    pub texture_digger: Handle<Texture>,
-    pub texture_background: Handle<Texture>,
-    pub texture_border: Handle<Texture>,
-    pub texture_tank_upgrade: Handle<Texture>,
-    pub texture_stone: Handle<Texture>,
-    pub texture_stone_mining: Handle<Texture>,
-    pub texture_silver: Handle<Texture>,
-    pub texture_silver_mining: Handle<Texture>,
-    pub texture_gold: Handle<Texture>,
-    pub texture_gold_mining: Handle<Texture>,
-    pub texture_diamond: Handle<Texture>,
-    pub texture_diamond_mining: Handle<Texture>,
-    pub texture_base: Handle<Texture>,
-    pub texture_waste: Handle<Texture>,
+impl LoadingState {
+    fn errors(&self, asset_server: &AssetServer) -> Vec<String> {
+        if asset_server.get_load_state(&self.manifest) == LoadState::Failed {
+            return vec![format!("Failed to load {}", MANIFEST_PATH)];
+        }
+        let mut errors: Vec<String> = self
+            .groups
+            .iter()
+            .flat_map(|group| {
+                group
+                    .failed(asset_server)
+                    .into_iter()
+                    .map(move |index| format!("Failed to load {}", group.paths[index]))
+            })
+            .collect();
+        if let Some(error) = &self.packing_error {
+            errors.push(error.to_string());
+        }
+        errors
+    }
+
+    /// Loads the manifest or the failed assets again and packs the textures once more
+    fn retry(&mut self, asset_server: &AssetServer) {
+        self.packing_error = None;
+        if asset_server.get_load_state(&self.manifest) == LoadState::Failed {
+            self.manifest = asset_server.load(MANIFEST_PATH);
+            return;
+        }
+        for group in self.groups.iter_mut() {
+            for index in group.failed(asset_server) {
+                group.handles[index] = asset_server.load_untyped(group.paths[index].as_str());
+            }
+        }
+    }
 }
 
-impl TextureAssets {
-    pub fn get_tile_handle(&self, mineral: &Tile) -> Handle<Texture> {
-        match mineral {
-            &Tile::Stone => self.texture_stone.clone(),
-            &Tile::Gold => self.texture_gold.clone(),
-            &Tile::Diamond => self.texture_diamond.clone(),
-            &Tile::Silver => self.texture_silver.clone(),
-            &Tile::Background => self.texture_background.clone(),
-            &Tile::Border => self.texture_border.clone(),
-            &Tile::TankUpgrade => self.texture_tank_upgrade.clone(),
-            &Tile::Base => self.texture_base.clone(),
-            &Tile::Waste => self.texture_waste.clone(),
+pub type ThemeAssets = AssetCollection<UiTheme>;
+pub type LocaleAssets = AssetCollection<Locale>;
+pub type AudioAssets = AssetCollection<AudioSource>;
+pub type TextureAssets = AssetCollection<Texture>;
+
+/// Every texture of the manifest packed into one atlas at load time
+///
+/// Tiles and the digger are drawn from this atlas, so they all share one texture.
+pub struct SpriteAtlas {
+    pub atlas: Handle<TextureAtlas>,
+    /// Index in the atlas by texture key
+    indices: HashMap<String, u32>,
+}
+
+impl SpriteAtlas {
+    /// Keys used in code but missing in the manifest are logged and give the first texture
+    pub fn index(&self, key: &str) -> u32 {
+        match self.indices.get(key) {
+            Some(index) => *index,
+            None => {
+                error!("Texture `{}` is missing in the manifest", key);
+                0
+            }
         }
     }
-    pub fn get_mining_tile_handle(&self, mineral: &Tile) -> Option<Handle<Texture>> {
+
+    pub fn tile_index(&self, mineral: &Tile) -> u32 {
+        self.index(match mineral {
+            &Tile::Stone => "stone",
+            &Tile::Gold => "gold",
+            &Tile::Diamond => "diamond",
+            &Tile::Silver => "silver",
+            &Tile::Background => "background",
+            &Tile::Border => "border",
+            &Tile::TankUpgrade => "tank_upgrade",
+            &Tile::Base => "base",
+            &Tile::Waste => "waste",
+        })
+    }
+
+    pub fn mining_tile_index(&self, mineral: &Tile) -> Option<u32> {
         match mineral {
-            &Tile::Stone => Some(self.texture_stone_mining.clone()),
-            &Tile::Silver => Some(self.texture_silver_mining.clone()),
-            &Tile::Gold => Some(self.texture_gold_mining.clone()),
-            &Tile::Diamond => Some(self.texture_diamond_mining.clone()),
+            &Tile::Stone => Some(self.index("stone_mining")),
+            &Tile::Silver => Some(self.index("silver_mining")),
+            &Tile::Gold => Some(self.index("gold_mining")),
+            &Tile::Diamond => Some(self.index("diamond_mining")),
             _ => None,
         }
     }
 }
 
-fn start_loading(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let mut fonts: Vec<HandleUntyped> = vec![];
-    fonts.push(asset_server.load_untyped(PATHS.fira_sans));
-
-    let mut audio: Vec<HandleUntyped> = vec![];
-    audio.push(asset_server.load_untyped(PATHS.audio_digging));
-    audio.push(asset_server.load_untyped(PATHS.audio_flying));
-    audio.push(asset_server.load_untyped(PATHS.audio_fuel));
-    audio.push(asset_server.load_untyped(PATHS.audio_waste));
-
-    let mut textures: Vec<HandleUntyped> = vec![];
-    textures.push(asset_server.load_untyped(PATHS.texture_digger));
-    textures.push(asset_server.load_untyped(PATHS.texture_background));
-    textures.push(asset_server.load_untyped(PATHS.texture_tank_upgrade));
-    textures.push(asset_server.load_untyped(PATHS.texture_border));
-    textures.push(asset_server.load_untyped(PATHS.texture_stone));
-    textures.push(asset_server.load_untyped(PATHS.texture_stone_mining));
-    textures.push(asset_server.load_untyped(PATHS.texture_silver));
-    textures.push(asset_server.load_untyped(PATHS.texture_silver_mining));
-    textures.push(asset_server.load_untyped(PATHS.texture_gold));
-    textures.push(asset_server.load_untyped(PATHS.texture_gold_mining));
-    textures.push(asset_server.load_untyped(PATHS.texture_diamond));
-    textures.push(asset_server.load_untyped(PATHS.texture_diamond_mining));
-    textures.push(asset_server.load_untyped(PATHS.texture_base));
-    textures.push(asset_server.load_untyped(PATHS.texture_waste));
+#[derive(Debug)]
+enum PackingError {
+    /// The texture with this key is not in storage
+    MissingTexture(String),
+    Builder(TextureAtlasBuilderError),
+}
+
+impl fmt::Display for PackingError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PackingError::MissingTexture(key) => {
+                write!(
+                    formatter,
+                    "Failed to pack the textures: `{}` is missing",
+                    key
+                )
+            }
+            PackingError::Builder(error) => {
+                write!(formatter, "Failed to pack the textures: {}", error)
+            }
+        }
+    }
+}
+
+/// Packs every texture of the manifest and looks up their indices in the atlas
+fn pack_textures(
+    texture_assets: &TextureAssets,
+    textures: &mut Assets<Texture>,
+) -> Result<(TextureAtlas, HashMap<String, u32>), PackingError> {
+    let mut builder = TextureAtlasBuilder::default();
+    for (key, handle) in texture_assets.iter() {
+        let texture = textures
+            .get(handle)
+            .ok_or_else(|| PackingError::MissingTexture(key.clone()))?;
+        builder.add_texture(handle.clone(), texture);
+    }
+    let atlas = builder.finish(textures).map_err(PackingError::Builder)?;
+    let mut indices = HashMap::new();
+    for (key, handle) in texture_assets.iter() {
+        let index = atlas
+            .get_texture_index(handle)
+            .ok_or_else(|| PackingError::MissingTexture(key.clone()))?;
+        indices.insert(key.clone(), index as u32);
+    }
+    Ok((atlas, indices))
+}
 
+fn start_loading(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut fonts: ResMut<Assets<Font>>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let font = Font::try_from_bytes(LOADING_FONT.to_vec()).expect("the built in font is valid");
     commands.insert_resource(LoadingState {
-        textures,
-        fonts,
-        audio,
+        manifest: asset_server.load(MANIFEST_PATH),
+        font: fonts.add(font),
+        groups: vec![],
+        packing_error: None,
     });
+
+    commands
+        .spawn_bundle(UiCameraBundle::default())
+        .insert(LoadingIndicator);
+    spawn_screen(&mut commands, &mut color_materials, BACKGROUND_COLOR)
+        .insert(LoadingIndicator)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::ColumnReverse,
+                        ..Default::default()
+                    },
+                    material: color_materials.add(Color::NONE.into()),
+                    ..Default::default()
+                })
+                .insert(ProgressBars);
+            parent
+                .spawn_bundle(TextBundle {
+                    style: Style {
+                        margin: Rect::all(Val::Px(20.)),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .insert(LoadingErrors);
+        });
 }
 
 fn check_state(
     mut commands: Commands,
     mut state: ResMut<State<GameState>>,
     asset_server: Res<AssetServer>,
-    loading_state: Res<LoadingState>,
+    mut loading_state: ResMut<LoadingState>,
+    manifests: Res<Assets<AssetManifest>>,
+    themes: Res<Assets<UiTheme>>,
+    locales: Res<Assets<Locale>>,
+    mut textures: ResMut<Assets<Texture>>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
 ) {
-    if LoadState::Loaded
-        != asset_server.get_group_load_state(loading_state.fonts.iter().map(|handle| handle.id))
-    {
+    let manifest = match manifests.get(&loading_state.manifest) {
+        Some(manifest) => manifest,
+        None => return,
+    };
+    // packing again only happens on retry
+    if loading_state.packing_error.is_some() {
         return;
     }
+    if loading_state.groups.is_empty() {
+        loading_state.groups = manifest
+            .groups()
+            .into_iter()
+            .map(|(name, paths)| AssetGroup {
+                name,
+                paths: paths.values().cloned().collect(),
+                handles: paths
+                    .values()
+                    .map(|path| asset_server.load_untyped(path.as_str()))
+                    .collect(),
+            })
+            .collect();
+        return;
+    }
+    // failed assets keep the game on the loading screen until they are retried
     if LoadState::Loaded
-        != asset_server.get_group_load_state(loading_state.textures.iter().map(|handle| handle.id))
+        != asset_server.get_group_load_state(
+            loading_state
+                .groups
+                .iter()
+                .flat_map(|group| group.handles.iter().map(|handle| handle.id)),
+        )
     {
         return;
     }
-    if LoadState::Loaded
-        != asset_server.get_group_load_state(loading_state.audio.iter().map(|handle| handle.id))
+
+    let theme_assets = ThemeAssets::new(&asset_server, &manifest.themes);
+    let locale_assets = LocaleAssets::new(&asset_server, &manifest.locales);
+    // the menu needs the selected theme and language right away,
+    // but loaded assets only show up in storage a bit later
+    let texture_assets = TextureAssets::new(&asset_server, &manifest.textures);
+    if !theme_assets.is_stored(&themes)
+        || !locale_assets.is_stored(&locales)
+        || !texture_assets.is_stored(&textures)
     {
         return;
     }
 
-    commands.insert_resource(FontAssets {
-        fira_sans: asset_server.get_handle(PATHS.fira_sans),
+    let (atlas, indices) = match pack_textures(&texture_assets, &mut textures) {
+        Ok(packed) => packed,
+        Err(error) => {
+            error!("{}", error);
+            loading_state.packing_error = Some(error);
+            return;
+        }
+    };
+    commands.insert_resource(SpriteAtlas {
+        atlas: texture_atlases.add(atlas),
+        indices,
     });
+    commands.insert_resource(theme_assets);
+    commands.insert_resource(locale_assets);
+    commands.insert_resource(AudioAssets::new(&asset_server, &manifest.audio));
+    commands.insert_resource(texture_assets);
 
-    commands.insert_resource(AudioAssets {
-        digging: asset_server.get_handle(PATHS.audio_digging),
-        flying: asset_server.get_handle(PATHS.audio_flying),
-        waste: asset_server.get_handle(PATHS.audio_waste),
-        fuel: asset_server.get_handle(PATHS.audio_fuel),
-    });
+    state.set(GameState::Menu).unwrap();
+}
 
-    commands.insert_resource(TextureAssets {
-        texture_base: asset_server.get_handle(PATHS.texture_base),
-        texture_digger: asset_server.get_handle(PATHS.texture_digger),
-        texture_background: asset_server.get_handle(PATHS.texture_background),
-        texture_tank_upgrade: asset_server.get_handle(PATHS.texture_tank_upgrade),
-        texture_border: asset_server.get_handle(PATHS.texture_border),
-        texture_stone: asset_server.get_handle(PATHS.texture_stone),
-        texture_stone_mining: asset_server.get_handle(PATHS.texture_stone_mining),
-        texture_silver: asset_server.get_handle(PATHS.texture_silver),
-        texture_silver_mining: asset_server.get_handle(PATHS.texture_silver_mining),
-        texture_gold: asset_server.get_handle(PATHS.texture_gold),
-        texture_gold_mining: asset_server.get_handle(PATHS.texture_gold_mining),
-        texture_diamond: asset_server.get_handle(PATHS.texture_diamond),
-        texture_diamond_mining: asset_server.get_handle(PATHS.texture_diamond_mining),
-        texture_waste: asset_server.get_handle(PATHS.texture_waste),
-    });
+/// Adds a progress bar per asset group once the manifest is loaded and keeps them up to date
+fn show_progress(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    loading_state: Res<LoadingState>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+    bars_query: Query<(Entity, Option<&Children>), With<ProgressBars>>,
+    mut bar_query: Query<(&ProgressBar, &mut Style, &Handle<ColorMaterial>)>,
+    mut errors_query: Query<&mut Text, With<LoadingErrors>>,
+) {
+    let style = TextStyle {
+        font: loading_state.font.clone(),
+        font_size: FONT_SIZE,
+        color: TEXT_COLOR,
+    };
 
-    state.set(GameState::Menu).unwrap();
+    for (entity, children) in bars_query.iter() {
+        if children.map_or(0, |children| children.len()) > 0 || loading_state.groups.is_empty() {
+            continue;
+        }
+        commands.entity(entity).with_children(|parent| {
+            for (index, group) in loading_state.groups.iter().enumerate() {
+                spawn_progress_bar(parent, &mut color_materials, &style, group.name, index);
+            }
+        });
+    }
+
+    for (bar, mut bar_style, material) in bar_query.iter_mut() {
+        let group = &loading_state.groups[bar.0];
+        let share = if group.handles.is_empty() {
+            1.
+        } else {
+            group.loaded(&asset_server) as f32 / group.handles.len() as f32
+        };
+        bar_style.size.width = Val::Percent(share * 100.);
+        let color = if group.failed(&asset_server).is_empty() {
+            BAR_COLOR
+        } else {
+            ERROR_COLOR
+        };
+        if let Some(material) = color_materials.get_mut(material) {
+            if material.color != color {
+                material.color = color;
+            }
+        }
+    }
+
+    let errors = loading_state.errors(&asset_server);
+    let message = if errors.is_empty() {
+        "".to_owned()
+    } else {
+        let mut message: String = errors.iter().map(|error| format!("{}\n", error)).collect();
+        message.push_str("Press R to retry or Escape to quit");
+        message
+    };
+    for mut text in errors_query.iter_mut() {
+        if text.sections.first().map(|section| &section.value) != Some(&message) {
+            *text = Text::with_section(
+                message.clone(),
+                TextStyle {
+                    color: ERROR_COLOR,
+                    ..style.clone()
+                },
+                Default::default(),
+            );
+        }
+    }
+}
+
+fn spawn_progress_bar(
+    parent: &mut ChildBuilder,
+    color_materials: &mut Assets<ColorMaterial>,
+    style: &TextStyle,
+    name: &str,
+    index: usize,
+) {
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                margin: Rect::all(Val::Px(5.)),
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: color_materials.add(Color::NONE.into()),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                style: Style {
+                    size: Size::new(Val::Px(150.), Val::Auto),
+                    ..Default::default()
+                },
+                text: Text::with_section(name, style.clone(), Default::default()),
+                ..Default::default()
+            });
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(300.), Val::Px(16.)),
+                        ..Default::default()
+                    },
+                    material: color_materials.add(BAR_BACKGROUND_COLOR.into()),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(0.), Val::Percent(100.)),
+                                ..Default::default()
+                            },
+                            material: color_materials.add(BAR_COLOR.into()),
+                            ..Default::default()
+                        })
+                        .insert(ProgressBar(index));
+                });
+        });
+}
+
+fn retry_or_quit(
+    keyboard_input: Res<Input<KeyCode>>,
+    asset_server: Res<AssetServer>,
+    mut loading_state: ResMut<LoadingState>,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    if keyboard_input.just_pressed(QUIT_KEY) {
+        app_exit.send(AppExit);
+    } else if keyboard_input.just_pressed(RETRY_KEY)
+        && !loading_state.errors(&asset_server).is_empty()
+    {
+        loading_state.retry(&asset_server);
+    }
+}
+
+/// Packs the atlas again when one of its textures changed on disk
+///
+/// Sprites already drawn from the atlas are moved to the new index of their texture.
+/// If packing fails, the old atlas stays.
+fn repack_textures(
+    mut events: EventReader<AssetEvent<Texture>>,
+    sprite_atlas: Option<ResMut<SpriteAtlas>>,
+    texture_assets: Option<Res<TextureAssets>>,
+    mut textures: ResMut<Assets<Texture>>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    mut sprite_query: Query<(&Handle<TextureAtlas>, &mut TextureAtlasSprite)>,
+) {
+    let (mut sprite_atlas, texture_assets) = match (sprite_atlas, texture_assets) {
+        (Some(sprite_atlas), Some(texture_assets)) => (sprite_atlas, texture_assets),
+        _ => return,
+    };
+    let modified = events.iter().any(|event| match event {
+        AssetEvent::Modified { handle } => {
+            texture_assets.iter().any(|(_, texture)| texture == handle)
+        }
+        _ => false,
+    });
+    if !modified {
+        return;
+    }
+    let (atlas, indices) = match pack_textures(&texture_assets, &mut textures) {
+        Ok(packed) => packed,
+        Err(error) => {
+            error!("{}", error);
+            return;
+        }
+    };
+    let new_indices: HashMap<u32, u32> = sprite_atlas
+        .indices
+        .iter()
+        .filter_map(|(key, old)| indices.get(key).map(|new| (*old, *new)))
+        .collect();
+    for (handle, mut sprite) in sprite_query.iter_mut() {
+        if *handle != sprite_atlas.atlas {
+            continue;
+        }
+        if let Some(index) = new_indices.get(&sprite.index) {
+            if sprite.index != *index {
+                sprite.index = *index;
+            }
+        }
+    }
+    texture_atlases.set(&sprite_atlas.atlas, atlas);
+    sprite_atlas.indices = indices;
 }
 
 fn clean_up_loading(mut commands: Commands, text_query: Query<Entity, With<LoadingIndicator>>) {
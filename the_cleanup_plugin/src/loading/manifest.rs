@@ -0,0 +1,96 @@
+use bevy::asset::{Asset, AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+
+pub const MANIFEST_PATH: &str = "game.manifest";
+
+/// Keys and paths of every asset loaded before the menu, by category
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "9c08300c-b150-41a2-9236-92f21b87de50"]
+pub struct AssetManifest {
+    pub themes: BTreeMap<String, String>,
+    pub locales: BTreeMap<String, String>,
+    pub audio: BTreeMap<String, String>,
+    pub textures: BTreeMap<String, String>,
+}
+
+impl AssetManifest {
+    /// Categories with a name for the loading screen, in the order they are shown
+    pub fn groups(&self) -> Vec<(&'static str, &BTreeMap<String, String>)> {
+        vec![
+            ("Themes", &self.themes),
+            ("Languages", &self.locales),
+            ("Audio", &self.audio),
+            ("Textures", &self.textures),
+        ]
+    }
+}
+
+#[derive(Default)]
+pub struct AssetManifestLoader;
+
+impl AssetLoader for AssetManifestLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let manifest: AssetManifest = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(manifest));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["manifest"]
+    }
+}
+
+/// Handles of one category of the manifest by their key
+pub struct AssetCollection<T: Asset> {
+    handles: HashMap<String, Handle<T>>,
+}
+
+impl<T: Asset> AssetCollection<T> {
+    pub fn new(asset_server: &AssetServer, paths: &BTreeMap<String, String>) -> Self {
+        AssetCollection {
+            handles: paths
+                .iter()
+                .map(|(key, path)| (key.clone(), asset_server.get_handle(path.as_str())))
+                .collect(),
+        }
+    }
+
+    /// Keys used in code but missing in the manifest are logged and give an empty handle
+    pub fn get(&self, key: &str) -> Handle<T> {
+        match self.handles.get(key) {
+            Some(handle) => handle.clone(),
+            None => {
+                error!("Asset `{}` is missing in the manifest", key);
+                Handle::default()
+            }
+        }
+    }
+
+    /// Falls back to the asset with the key `fallback` for unknown keys
+    pub fn get_or(&self, key: &str, fallback: &str) -> Handle<T> {
+        match self.handles.get(key) {
+            Some(handle) => handle.clone(),
+            None => self.get(fallback),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Handle<T>)> {
+        self.handles.iter()
+    }
+
+    /// Whether every asset of the collection is already in storage
+    pub fn is_stored(&self, assets: &Assets<T>) -> bool {
+        self.handles
+            .values()
+            .all(|handle| assets.get(handle).is_some())
+    }
+}
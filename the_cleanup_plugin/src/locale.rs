@@ -0,0 +1,285 @@
+use crate::loading::LocaleAssets;
+use crate::settings::Settings;
+use crate::GameState;
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use std::collections::HashMap;
+
+/// Keys of the locales in `assets/game.manifest` and the name of the language in that language
+pub const LANGUAGES: [(&str, &str); 2] = [("en", "English"), ("de", "Deutsch")];
+
+/// Messages missing in a translation are taken from this language
+const FALLBACK_LANGUAGE: &str = "en";
+
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_asset::<Locale>()
+            .init_asset_loader::<LocaleLoader>()
+            // the menu needs its strings as soon as loading is done
+            .add_system_set(
+                SystemSet::on_exit(GameState::Loading).with_system(select_locale.system()),
+            )
+            .add_system(select_locale.system());
+    }
+}
+
+/// Messages of one language, read from a `.lang` string table
+///
+/// Every line is a `key = text` message, a `-key = text` term or a `#` comment. Texts can hold
+/// variable (`{ $name }`), term (`{ -name }`) and string literal (`{ "text" }`) placeables;
+/// there are no plural or select expressions. The selected language is also available as resource.
+#[derive(TypeUuid, Clone)]
+#[uuid = "5d3c0a4e-8f47-4c8b-a0b5-3e6f9c2d7b18"]
+pub struct Locale {
+    pub language: String,
+    messages: HashMap<String, String>,
+}
+
+impl Locale {
+    /// The message without any variables
+    pub fn get(&self, key: &str) -> String {
+        self.format(key, &[])
+    }
+
+    /// The message with its placeables filled in. Unknown keys are returned as they are.
+    pub fn format(&self, key: &str, args: &[(&str, String)]) -> String {
+        self.expand(key, args, &mut vec![])
+    }
+
+    /// `expanding` holds the messages and terms that are currently being expanded;
+    /// terms referencing one of them are left as they are instead of recursing forever
+    fn expand<'a>(
+        &'a self,
+        key: &'a str,
+        args: &[(&str, String)],
+        expanding: &mut Vec<&'a str>,
+    ) -> String {
+        let pattern = match self.messages.get(key) {
+            Some(pattern) => pattern,
+            None => return key.to_owned(),
+        };
+        expanding.push(key);
+        let mut result = String::new();
+        let mut rest = pattern.as_str();
+        while let Some(start) = rest.find('{') {
+            result.push_str(&rest[..start]);
+            let end = match rest[start..].find('}') {
+                Some(end) => start + end,
+                None => break,
+            };
+            let placeable = rest[start + 1..end].trim();
+            if let Some(name) = placeable.strip_prefix('$') {
+                match args.iter().find(|(arg, _)| *arg == name) {
+                    Some((_, value)) => result.push_str(value),
+                    None => result.push_str(placeable),
+                }
+            } else if placeable.starts_with('-') && !expanding.contains(&placeable) {
+                result.push_str(&self.expand(placeable, args, expanding));
+            } else {
+                result.push_str(placeable.trim_matches('"'));
+            }
+            rest = &rest[end + 1..];
+        }
+        result.push_str(rest);
+        expanding.pop();
+        result
+    }
+
+    /// Number with the decimal and digit group separators of the language
+    pub fn number(&self, value: impl Into<f64>, decimals: usize) -> String {
+        let value = value.into();
+        let digits = format!("{:.*}", decimals, value.abs());
+        let (integer, fraction) = match digits.find('.') {
+            Some(point) => (&digits[..point], Some(&digits[point + 1..])),
+            None => (digits.as_str(), None),
+        };
+        let group_separator = self.get("-group-separator");
+        let mut result = String::new();
+        if value < 0. && digits.chars().any(|digit| digit != '0' && digit != '.') {
+            result.push('-');
+        }
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && (integer.len() - index) % 3 == 0 {
+                result.push_str(&group_separator);
+            }
+            result.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            result.push_str(&self.get("-decimal-separator"));
+            result.push_str(fraction);
+        }
+        result
+    }
+
+    /// Whole amount of money with the currency sign where the language puts it
+    pub fn money(&self, value: impl Into<f64>) -> String {
+        self.format("-currency", &[("amount", self.number(value, 0))])
+    }
+}
+
+#[derive(Default)]
+pub struct LocaleLoader;
+
+impl AssetLoader for LocaleLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let language = load_context
+                .path()
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or_default()
+                .to_owned();
+            let locale = Locale {
+                language,
+                messages: parse_messages(std::str::from_utf8(bytes)?)?,
+            };
+            load_context.set_default_asset(LoadedAsset::new(locale));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["lang"]
+    }
+}
+
+fn parse_messages(source: &str) -> Result<HashMap<String, String>, anyhow::Error> {
+    let mut messages = HashMap::new();
+    for (number, line) in source.lines().enumerate() {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = match line.find('=') {
+            Some(equals) => (line[..equals].trim(), line[equals + 1..].trim()),
+            None => anyhow::bail!("line {}: expected `key = value`", number + 1),
+        };
+        let valid_key = key
+            .trim_start_matches('-')
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || character == '-');
+        if key.is_empty() || !valid_key || line.starts_with(char::is_whitespace) {
+            anyhow::bail!("line {}: unsupported message `{}`", number + 1, key);
+        }
+        messages.insert(key.to_owned(), value.to_owned());
+    }
+    Ok(messages)
+}
+
+fn select_locale(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    locale_assets: Option<Res<LocaleAssets>>,
+    locales: Res<Assets<Locale>>,
+    current: Option<Res<Locale>>,
+) {
+    let locale_assets = match locale_assets {
+        Some(locale_assets) => locale_assets,
+        None => return,
+    };
+    let fallback = locales.get(&locale_assets.get(FALLBACK_LANGUAGE));
+    let selected = locales.get(&locale_assets.get_or(&settings.language, FALLBACK_LANGUAGE));
+    let (fallback, selected) = match (fallback, selected) {
+        (Some(fallback), Some(selected)) => (fallback, selected),
+        _ => return,
+    };
+    if current.map(|current| current.language == selected.language) == Some(true) {
+        return;
+    }
+    let mut locale = fallback.clone();
+    locale.language = selected.language.clone();
+    locale.messages.extend(selected.messages.clone());
+    commands.insert_resource(locale);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locale(source: &str) -> Locale {
+        Locale {
+            language: "test".to_owned(),
+            messages: parse_messages(source).unwrap(),
+        }
+    }
+
+    fn english() -> Locale {
+        locale("-decimal-separator = .\n-group-separator = ,\n-currency = $ { $amount }")
+    }
+
+    fn german() -> Locale {
+        locale("-decimal-separator = ,\n-group-separator = .\n-currency = { $amount } $")
+    }
+
+    #[test]
+    fn parses_messages_and_skips_comments() {
+        let messages =
+            parse_messages("# comment\n\nhello = Hello { $name }!\n-term = Term\n").unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages["hello"], "Hello { $name }!");
+        assert_eq!(messages["-term"], "Term");
+    }
+
+    #[test]
+    fn rejects_unsupported_lines() {
+        assert!(parse_messages("no equals sign").is_err());
+        assert!(parse_messages("key = value\n    continued = line").is_err());
+        assert!(parse_messages("in valid = key").is_err());
+        assert!(parse_messages("= value").is_err());
+    }
+
+    #[test]
+    fn fills_in_placeables() {
+        let locale =
+            locale("-name = The Cleanup\ngreeting = Welcome to { -name }, { $player }{ \"!\" }");
+        assert_eq!(
+            locale.format("greeting", &[("player", "Robot".to_owned())]),
+            "Welcome to The Cleanup, Robot!"
+        );
+        // missing variables stay visible
+        assert_eq!(locale.get("greeting"), "Welcome to The Cleanup, $player!");
+    }
+
+    #[test]
+    fn returns_unknown_keys() {
+        assert_eq!(english().get("missing-key"), "missing-key");
+    }
+
+    #[test]
+    fn stops_at_recursive_terms() {
+        let locale = locale("-self = a { -self }\n-a = a { -b }\n-b = b { -a }\nmessage = { -a }");
+        assert_eq!(locale.get("-self"), "a -self");
+        assert_eq!(locale.get("message"), "a b -a");
+    }
+
+    #[test]
+    fn groups_digits() {
+        let locale = english();
+        assert_eq!(locale.number(0, 0), "0");
+        assert_eq!(locale.number(999, 0), "999");
+        assert_eq!(locale.number(1000, 0), "1,000");
+        assert_eq!(locale.number(1234567.891, 2), "1,234,567.89");
+        assert_eq!(german().number(-1234.5, 1), "-1.234,5");
+    }
+
+    #[test]
+    fn drops_the_sign_of_negative_zero() {
+        let locale = english();
+        assert_eq!(locale.number(-0.4, 0), "0");
+        assert_eq!(locale.number(-0.04, 1), "0.0");
+        assert_eq!(locale.number(-0.5, 1), "-0.5");
+    }
+
+    #[test]
+    fn places_the_currency_sign() {
+        assert_eq!(english().money(1500.4), "$ 1,500");
+        assert_eq!(german().money(1500.4), "1.500 $");
+    }
+}
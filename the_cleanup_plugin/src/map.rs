@@ -1,10 +1,14 @@
 use crate::GameState;
 use bevy::prelude::*;
 
-use crate::loading::TextureAssets;
+use crate::loading::SpriteAtlas;
+use crate::rules::GameRules;
 use rand::distributions::Standard;
 use rand::prelude::Distribution;
-use rand::{random, thread_rng, Rng};
+use rand::seq::SliceRandom;
+use rand::{random, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
 
 pub struct MapPlugin;
 
@@ -15,25 +19,35 @@ pub enum MapSystemLabels {
 
 impl Plugin for MapPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_system_set(
-            SystemSet::on_enter(GameState::Playing)
-                .with_system(generate_map.exclusive_system())
-                .with_system(spawn_camera.system())
-                .with_system(render_map.system()),
-        )
-        .add_system_set(
-            SystemSet::on_exit(GameState::Playing).with_system(
-                remove_map
-                    .system()
-                    .label(MapSystemLabels::DespawnMapAndCamera),
-            ),
-        );
+        app.init_resource::<MapSeed>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(generate_map.exclusive_system())
+                    .with_system(spawn_camera.system())
+                    .with_system(render_map.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Playing).with_system(
+                    remove_map
+                        .system()
+                        .label(MapSystemLabels::DespawnMapAndCamera),
+                ),
+            );
     }
 }
 
 pub struct PlayerCamera;
 
-#[derive(PartialEq, Clone)]
+/// The whole map is derived from this seed, so the same seed always generates the same map
+pub struct MapSeed(pub u64);
+
+impl Default for MapSeed {
+    fn default() -> Self {
+        MapSeed(random())
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
 pub enum Tile {
     Background,
     Border,
@@ -53,6 +67,21 @@ pub enum MiningEffect {
 }
 
 impl Tile {
+    /// Key of the tile name in the locale files
+    pub fn name_key(&self) -> &'static str {
+        match self {
+            &Tile::Background => "tile-background",
+            &Tile::Border => "tile-border",
+            &Tile::TankUpgrade => "tile-tank-upgrade",
+            &Tile::Base => "tile-base",
+            &Tile::Stone => "tile-stone",
+            &Tile::Gold => "tile-gold",
+            &Tile::Waste => "tile-waste",
+            &Tile::Diamond => "tile-diamond",
+            &Tile::Silver => "tile-silver",
+        }
+    }
+
     pub fn collides(&self) -> bool {
         match self {
             &Tile::Stone => true,
@@ -113,19 +142,45 @@ pub struct Map {
     pub tile_size: f32,
 }
 
+impl Map {
+    /// Number of tiles between the surface and the given world y coordinate
+    pub fn depth_at(&self, y: f32) -> usize {
+        ((self.base.y - y) / self.tile_size).round().max(0.) as usize
+    }
+
+    /// Depth of the lowest row above the bottom border
+    pub fn max_depth(&self) -> usize {
+        self.depth_at(self.tile_size)
+    }
+
+    /// Number of tile columns
+    pub fn width(&self) -> usize {
+        self.dimensions.x
+    }
+
+    /// Number of tile rows
+    pub fn height(&self) -> usize {
+        self.dimensions.y
+    }
+}
+
 pub struct MapTile {
     pub x: usize,
     pub y: usize,
 }
 
-fn generate_map(mut commands: Commands) {
+fn generate_map(mut commands: Commands, rules: Res<GameRules>, seed: Res<MapSeed>) {
     let mut map = Map {
-        dimensions: Dimensions { x: 50, y: 100 },
+        dimensions: Dimensions {
+            x: 50,
+            y: rules.map_depth,
+        },
         tiles: vec![],
         tile_size: 32.,
-        base: Vec2::new(24.5 * 32., 88. * 32.),
+        base: Vec2::new(24.5 * 32., (rules.map_depth - 12) as f32 * 32.),
     };
-    let mut rng = thread_rng();
+    // unlike StdRng, ChaCha8Rng is portable, so the same seed gives the same map on every platform
+    let mut rng = ChaCha8Rng::seed_from_u64(seed.0);
 
     map.tiles.push(
         vec![Tile::Border]
@@ -163,7 +218,7 @@ fn generate_map(mut commands: Commands) {
         let mut row: Vec<Tile> = vec![];
         row.push(Tile::Border);
         for _column in 1..map.dimensions.x - 1 {
-            row.push(random::<Tile>().clone());
+            row.push(rng.gen::<Tile>());
         }
         row.push(Tile::Border);
         map.tiles.push(row);
@@ -178,8 +233,8 @@ fn generate_map(mut commands: Commands) {
     );
     map.tiles.reverse();
 
-    // distribute 6 tank extensions
-    for _depth in 0..5 {
+    // distribute tank extensions; one for every 20 rows and one close to the surface
+    for _depth in 0..map.dimensions.y / 20 {
         let x: usize = rng.gen_range(1..map.dimensions.x - 1);
         let y: usize = rng.gen_range(1..map.dimensions.y - 13);
 
@@ -188,11 +243,21 @@ fn generate_map(mut commands: Commands) {
     let x: usize = rng.gen_range(1..map.dimensions.x - 1);
     map.tiles[map.dimensions.y - 15][x] = Tile::TankUpgrade;
 
-    // distribute 10 waste
-    for _depth in 0..10 {
-        let x: usize = rng.gen_range(1..map.dimensions.x - 1);
-        let y: usize = rng.gen_range(1..map.dimensions.y - 13);
-
+    // distribute the waste on distinct tiles without replacing tank upgrades, so the
+    // objective can always be reached
+    let waste_target = rules.waste_target().unwrap_or(0);
+    let mut candidates: Vec<(usize, usize)> = (1..map.dimensions.y - 13)
+        .flat_map(|y| (1..map.dimensions.x - 1).map(move |x| (x, y)))
+        .filter(|&(x, y)| map.tiles[y][x] != Tile::TankUpgrade)
+        .collect();
+    assert!(
+        waste_target <= candidates.len(),
+        "the map has room for {} waste, but the rules ask for {}",
+        candidates.len(),
+        waste_target
+    );
+    candidates.shuffle(&mut rng);
+    for &(x, y) in candidates.iter().take(waste_target) {
         map.tiles[y][x] = Tile::Waste;
     }
 
@@ -208,20 +273,15 @@ fn spawn_camera(mut commands: Commands, map: Res<Map>) {
         .insert(PlayerCamera);
 }
 
-fn render_map(
-    mut commands: Commands,
-    map: Res<Map>,
-    texture_assets: Res<TextureAssets>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
+fn render_map(mut commands: Commands, map: Res<Map>, sprite_atlas: Res<SpriteAtlas>) {
     for row in 0..map.dimensions.y {
         for column in 0..map.dimensions.x {
             let tile = &map.tiles[row as usize][column as usize];
 
-            let handle = texture_assets.get_tile_handle(tile);
             commands
-                .spawn_bundle(SpriteBundle {
-                    material: materials.add(handle.into()),
+                .spawn_bundle(SpriteSheetBundle {
+                    texture_atlas: sprite_atlas.atlas.clone(),
+                    sprite: TextureAtlasSprite::new(sprite_atlas.tile_index(tile)),
                     transform: Transform::from_translation(Vec3::new(
                         column as f32 * map.tile_size,
                         row as f32 * map.tile_size,
@@ -1,97 +1,172 @@
+use crate::daily::DailyChallenge;
+use crate::locale::Locale;
+use crate::map::MapSeed;
+use crate::persistence;
+use crate::rules::{GameMode, GameRules};
+use crate::theme::UiTheme;
+use crate::widgets::{
+    spawn_button, spawn_label, spawn_screen, ButtonActionPlugin, ButtonMaterials, LabelStyle,
+};
 use crate::GameState;
+use bevy::app::AppExit;
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const LAST_GAME_FILE: &str = "last_game.ron";
 
 pub struct MenuPlugin;
 
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.init_resource::<ButtonMaterials>()
+        app.add_plugin(ButtonActionPlugin::<MenuAction>::default())
+            .insert_resource(persistence::load::<LastGame>(LAST_GAME_FILE))
             .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(setup_menu.system()))
             .add_system_set(
-                SystemSet::on_update(GameState::Menu).with_system(click_play_button.system()),
+                SystemSet::on_update(GameState::Menu).with_system(handle_actions.system()),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Menu).with_system(remove_menu.system()))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(remember_game.system()),
             );
     }
 }
 
-struct ButtonMaterials {
-    normal: Handle<ColorMaterial>,
-    hovered: Handle<ColorMaterial>,
+/// Mode and seed of a run
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct GameSetup {
+    pub mode: GameMode,
+    pub seed: u64,
+    /// Day of the daily challenge, if the run is one
+    pub daily: Option<u64>,
 }
 
-impl FromWorld for ButtonMaterials {
-    fn from_world(world: &mut World) -> Self {
-        let mut materials = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
-        ButtonMaterials {
-            normal: materials.add(Color::rgb(0.15, 0.15, 0.15).into()),
-            hovered: materials.add(Color::rgb(0.25, 0.25, 0.25).into()),
+impl GameSetup {
+    pub fn apply(
+        &self,
+        mode: &mut GameMode,
+        rules: &mut GameRules,
+        seed: &mut MapSeed,
+        daily: &mut DailyChallenge,
+    ) {
+        *mode = self.mode;
+        *rules = GameRules::for_mode(mode);
+        seed.0 = self.seed;
+        // a past day's challenge is replayed as normal run, so its results stay final
+        match self.daily {
+            Some(day) if day == DailyChallenge::today() => {
+                daily.active = true;
+                daily.day = day;
+            }
+            _ => daily.active = false,
         }
     }
 }
 
-struct PlayButton;
+/// The most recently started run, replayed by "Continue"
+#[derive(Serialize, Deserialize, Default)]
+pub struct LastGame {
+    pub setup: Option<GameSetup>,
+}
+
+struct Menu;
+
+#[derive(Clone, Copy)]
+enum MenuAction {
+    Play,
+    Continue,
+    Settings,
+    Statistics,
+    Credits,
+    Quit,
+}
 
 fn setup_menu(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    theme: Res<UiTheme>,
+    locale: Res<Locale>,
     button_materials: Res<ButtonMaterials>,
+    last_game: Res<LastGame>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    commands.spawn_bundle(UiCameraBundle::default());
+    let mut buttons = vec![("menu-play", MenuAction::Play)];
+    if last_game.setup.is_some() {
+        buttons.push(("menu-continue", MenuAction::Continue));
+    }
+    buttons.push(("menu-settings", MenuAction::Settings));
+    buttons.push(("menu-statistics", MenuAction::Statistics));
+    buttons.push(("menu-credits", MenuAction::Credits));
+    buttons.push(("menu-quit", MenuAction::Quit));
+
     commands
-        .spawn_bundle(ButtonBundle {
-            style: Style {
-                size: Size::new(Val::Px(120.0), Val::Px(50.0)),
-                margin: Rect::all(Val::Auto),
-                justify_content: JustifyContent::Center,
-                align_items: AlignItems::Center,
-                ..Default::default()
-            },
-            material: button_materials.normal.clone(),
-            ..Default::default()
-        })
-        .insert(PlayButton)
-        .with_children(|parent| {
-            parent.spawn_bundle(TextBundle {
-                text: Text {
-                    sections: vec![TextSection {
-                        value: "Play".to_string(),
-                        style: TextStyle {
-                            font: asset_server.get_handle("fonts/FiraSans-Bold.ttf"),
-                            font_size: 40.0,
-                            color: Color::rgb(0.9, 0.9, 0.9),
-                            ..Default::default()
-                        },
-                    }],
-                    alignment: Default::default(),
-                },
-                ..Default::default()
-            });
-        });
+        .spawn_bundle(UiCameraBundle::default())
+        .insert(Menu);
+    spawn_screen(
+        &mut commands,
+        &mut color_materials,
+        theme.palette.background,
+    )
+    .insert(Menu)
+    .with_children(|parent| {
+        spawn_label(parent, &theme, locale.get("menu-title"), LabelStyle::Title);
+        for (label, action) in buttons {
+            spawn_button(
+                parent,
+                &button_materials,
+                &theme,
+                locale.get(label),
+                360.,
+                action,
+            );
+        }
+    });
 }
 
-fn click_play_button(
-    mut commands: Commands,
-    button_materials: Res<ButtonMaterials>,
+fn remember_game(
+    mode: Res<GameMode>,
+    seed: Res<MapSeed>,
+    daily: Res<DailyChallenge>,
+    mut last_game: ResMut<LastGame>,
+) {
+    let setup = GameSetup {
+        mode: *mode,
+        seed: seed.0,
+        daily: if daily.active { Some(daily.day) } else { None },
+    };
+    if last_game.setup.as_ref() != Some(&setup) {
+        last_game.setup = Some(setup);
+        persistence::save(LAST_GAME_FILE, &*last_game);
+    }
+}
+
+fn handle_actions(
+    mut actions: EventReader<MenuAction>,
     mut state: ResMut<State<GameState>>,
-    mut interaction_query: Query<
-        (Entity, &Interaction, &mut Handle<ColorMaterial>, &Children),
-        (Changed<Interaction>, With<Button>),
-    >,
-    text_query: Query<Entity, With<Text>>,
+    mut app_exit: EventWriter<AppExit>,
+    last_game: Res<LastGame>,
+    mut mode: ResMut<GameMode>,
+    mut rules: ResMut<GameRules>,
+    mut seed: ResMut<MapSeed>,
+    mut daily: ResMut<DailyChallenge>,
 ) {
-    for (button, interaction, mut material, children) in interaction_query.iter_mut() {
-        let text = text_query.get(children[0]).unwrap();
-        match *interaction {
-            Interaction::Clicked => {
-                commands.entity(button).despawn();
-                commands.entity(text).despawn();
-                state.set(GameState::Playing).unwrap();
-            }
-            Interaction::Hovered => {
-                *material = button_materials.hovered.clone();
-            }
-            Interaction::None => {
-                *material = button_materials.normal.clone();
+    for action in actions.iter() {
+        match action {
+            MenuAction::Play => state.set(GameState::NewGame).unwrap(),
+            MenuAction::Continue => {
+                if let Some(setup) = &last_game.setup {
+                    setup.apply(&mut mode, &mut rules, &mut seed, &mut daily);
+                    state.set(GameState::Playing).unwrap();
+                }
             }
+            MenuAction::Settings => state.set(GameState::Settings).unwrap(),
+            MenuAction::Statistics => state.set(GameState::Statistics).unwrap(),
+            MenuAction::Credits => state.set(GameState::Credits).unwrap(),
+            MenuAction::Quit => app_exit.send(AppExit),
         }
     }
 }
+
+fn remove_menu(mut commands: Commands, menu_query: Query<Entity, With<Menu>>) {
+    for entity in menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,188 @@
+use crate::digger::{Digger, DiggerSystemLabels, TileMined};
+use crate::map::{Map, Tile};
+use crate::visibility::{ExploredTiles, TileExplored, VisibilitySystemLabels};
+use crate::GameState;
+use bevy::prelude::*;
+use bevy::render::texture::{Extent3d, FilterMode, TextureDimension, TextureFormat};
+
+const MINIMAP_HEIGHT: f32 = 200.;
+const DIGGER_MARKER_SIZE: f32 = 6.;
+const UNEXPLORED: [u8; 4] = [0, 0, 0, 160];
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system_set(
+            SystemSet::on_enter(GameState::Playing).with_system(setup_minimap.system()),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(
+                    redraw_minimap
+                        .system()
+                        .after(VisibilitySystemLabels::Explore)
+                        .after(DiggerSystemLabels::Dig),
+                )
+                .with_system(move_digger_marker.system()),
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::Playing).with_system(remove_minimap.system()),
+        );
+    }
+}
+
+/// Handle of the texture with one pixel per map tile
+struct MinimapTexture(Handle<Texture>);
+
+struct Minimap;
+
+struct DiggerMarker;
+
+fn tile_color(tile: &Tile) -> [u8; 4] {
+    match tile {
+        Tile::Background => [20, 20, 30, 220],
+        Tile::Border => [60, 60, 60, 255],
+        Tile::Stone => [110, 100, 90, 255],
+        Tile::Silver => [190, 190, 200, 255],
+        Tile::Gold => [230, 190, 40, 255],
+        Tile::Diamond => [90, 220, 240, 255],
+        Tile::TankUpgrade => [60, 120, 255, 255],
+        Tile::Waste => [80, 255, 60, 255],
+        Tile::Base => [255, 60, 60, 255],
+    }
+}
+
+fn pixel_color(map: &Map, explored_tiles: &ExploredTiles, x: usize, y: usize) -> [u8; 4] {
+    if explored_tiles.is_explored(x, y) {
+        tile_color(&map.tiles[y][x])
+    } else {
+        UNEXPLORED
+    }
+}
+
+/// Byte offset of a tile's pixel; the texture starts at the top while map rows start at the bottom
+fn pixel_offset(map: &Map, x: usize, y: usize) -> usize {
+    ((map.height() - 1 - y) * map.width() + x) * 4
+}
+
+fn setup_minimap(
+    mut commands: Commands,
+    map: Res<Map>,
+    mut textures: ResMut<Assets<Texture>>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let mut texture = Texture::new_fill(
+        Extent3d::new(map.width() as u32, map.height() as u32, 1),
+        TextureDimension::D2,
+        &UNEXPLORED,
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    texture.sampler.mag_filter = FilterMode::Nearest;
+    let texture = textures.add(texture);
+    let width = MINIMAP_HEIGHT * map.width() as f32 / map.height() as f32;
+
+    commands
+        .spawn_bundle(ImageBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    right: Val::Px(10.),
+                    bottom: Val::Px(10.),
+                    ..Default::default()
+                },
+                size: Size::new(Val::Px(width), Val::Px(MINIMAP_HEIGHT)),
+                ..Default::default()
+            },
+            material: color_materials.add(texture.clone().into()),
+            ..Default::default()
+        })
+        .insert(Minimap)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        size: Size::new(Val::Px(DIGGER_MARKER_SIZE), Val::Px(DIGGER_MARKER_SIZE)),
+                        // centers the marker on its position
+                        margin: Rect {
+                            left: Val::Px(-DIGGER_MARKER_SIZE / 2.),
+                            top: Val::Px(-DIGGER_MARKER_SIZE / 2.),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    material: color_materials.add(Color::WHITE.into()),
+                    ..Default::default()
+                })
+                .insert(DiggerMarker);
+        });
+    commands.insert_resource(MinimapTexture(texture));
+}
+
+/// Redraws the tiles that were explored or mined since the last frame
+///
+/// The whole map is drawn once the exploration of a new run starts.
+fn redraw_minimap(
+    map: Res<Map>,
+    minimap_texture: Res<MinimapTexture>,
+    explored_tiles: Res<ExploredTiles>,
+    mut tile_explored: EventReader<TileExplored>,
+    mut tile_mined: EventReader<TileMined>,
+    mut textures: ResMut<Assets<Texture>>,
+) {
+    let changed_tiles: Vec<(usize, usize)> = if explored_tiles.is_added() {
+        (0..map.height())
+            .flat_map(|y| (0..map.width()).map(move |x| (x, y)))
+            .collect()
+    } else {
+        tile_explored
+            .iter()
+            .map(|event| (event.x, event.y))
+            .chain(tile_mined.iter().map(|event| (event.x, event.y)))
+            .collect()
+    };
+    if changed_tiles.is_empty() {
+        return;
+    }
+    if let Some(texture) = textures.get_mut(&minimap_texture.0) {
+        for (x, y) in changed_tiles {
+            let offset = pixel_offset(&map, x, y);
+            texture.data[offset..offset + 4].copy_from_slice(&pixel_color(
+                &map,
+                &explored_tiles,
+                x,
+                y,
+            ));
+        }
+    }
+}
+
+fn move_digger_marker(
+    map: Res<Map>,
+    digger_query: Query<&Transform, With<Digger>>,
+    mut marker_query: Query<&mut Style, With<DiggerMarker>>,
+) {
+    let transform = match digger_query.single() {
+        Ok(transform) => transform,
+        Err(_) => return,
+    };
+    let left =
+        Val::Percent((transform.translation.x / map.tile_size + 0.5) / map.width() as f32 * 100.);
+    let top = Val::Percent(
+        100. - (transform.translation.y / map.tile_size + 0.5) / map.height() as f32 * 100.,
+    );
+    for mut style in marker_query.iter_mut() {
+        if style.position.left != left || style.position.top != top {
+            style.position.left = left;
+            style.position.top = top;
+        }
+    }
+}
+
+fn remove_minimap(mut commands: Commands, query: Query<Entity, With<Minimap>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<MinimapTexture>();
+}
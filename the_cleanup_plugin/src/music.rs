@@ -0,0 +1,188 @@
+use crate::digger::{Digger, DiggerState};
+use crate::loading::AudioAssets;
+use crate::map::Map;
+use crate::settings::{Settings, VolumeSetting};
+use crate::ui::LOW_FUEL;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_kira_audio::{Audio, AudioChannel, AudioSource};
+
+/// Depth share of the map at which only the deep layer is left
+const DEEP_SHARE: f32 = 0.5;
+/// Distance in tiles to the base up to which the surface layer plays at full volume
+const BASE_RADIUS: f32 = 3.;
+/// Distance in tiles over which the surface layer fades out when leaving the base
+const BASE_FADE: f32 = 6.;
+/// How fast the layers crossfade
+const FADE_SPEED: f32 = 1.5;
+
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(MusicLayers::default())
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(start_music.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(mix_layers.system())
+                    .with_system(apply_layer_volumes.system())
+                    .with_system(reload_layers.system()),
+            )
+            .add_system_set(
+                SystemSet::on_pause(GameState::Playing).with_system(pause_music.system()),
+            )
+            .add_system_set(
+                SystemSet::on_resume(GameState::Playing).with_system(resume_music.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Playing).with_system(stop_music.system()),
+            );
+    }
+}
+
+/// Tracks of the same length and tempo that play together and are mixed by the situation
+#[derive(Clone, Copy)]
+enum Layer {
+    Surface,
+    Deep,
+    Danger,
+}
+
+const LAYERS: [Layer; 3] = [Layer::Surface, Layer::Deep, Layer::Danger];
+
+struct MusicLayers {
+    channels: Vec<AudioChannel>,
+    /// Current volume of each layer before the music volume setting
+    weights: Vec<f32>,
+    /// Volumes last sent to kira
+    applied_volumes: Vec<f32>,
+}
+
+impl Default for MusicLayers {
+    fn default() -> Self {
+        MusicLayers {
+            channels: vec![
+                AudioChannel::new("music_surface".to_owned()),
+                AudioChannel::new("music_deep".to_owned()),
+                AudioChannel::new("music_danger".to_owned()),
+            ],
+            weights: vec![0.; LAYERS.len()],
+            applied_volumes: vec![],
+        }
+    }
+}
+
+impl Layer {
+    fn source(&self, audio_assets: &AudioAssets) -> Handle<AudioSource> {
+        match self {
+            Layer::Surface => audio_assets.get("music_surface"),
+            Layer::Deep => audio_assets.get("music_deep"),
+            Layer::Danger => audio_assets.get("music_danger"),
+        }
+    }
+}
+
+fn start_music(
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+    mut music_layers: ResMut<MusicLayers>,
+) {
+    // the run starts at the base
+    music_layers.weights = vec![1., 0., 0.];
+    music_layers.applied_volumes = vec![];
+    for (layer, channel) in LAYERS.iter().zip(music_layers.channels.iter()) {
+        audio.set_volume_in_channel(0., channel);
+        audio.play_looped_in_channel(layer.source(&audio_assets), channel);
+    }
+}
+
+/// Moves the layer weights towards the mix for the current depth, fuel and distance to the base
+fn mix_layers(
+    time: Res<Time>,
+    map: Res<Map>,
+    digger_state: Res<DiggerState>,
+    mut music_layers: ResMut<MusicLayers>,
+    digger_query: Query<&Transform, With<Digger>>,
+) {
+    let digger = match digger_query.single() {
+        Ok(transform) => Vec2::new(transform.translation.x, transform.translation.y),
+        Err(_) => return,
+    };
+    let base_distance = digger.distance(map.base) / map.tile_size;
+    let near_base = (1. - (base_distance - BASE_RADIUS) / BASE_FADE).clamp(0., 1.);
+    let depth = (digger_state.depth as f32 / (map.max_depth() as f32 * DEEP_SHARE)).clamp(0., 1.);
+    let low_fuel = digger_state.fuel < digger_state.fuel_max * LOW_FUEL && !digger_state.dead;
+    let targets = [
+        near_base.max(1. - depth),
+        depth * (1. - near_base),
+        if low_fuel { 1. } else { 0. },
+    ];
+    let fade = 1. - (-FADE_SPEED * time.delta_seconds()).exp();
+    for (weight, target) in music_layers.weights.iter_mut().zip(targets.iter()) {
+        *weight += (target - *weight) * fade;
+    }
+}
+
+fn apply_layer_volumes(
+    audio: Res<Audio>,
+    settings: Res<Settings>,
+    mut music_layers: ResMut<MusicLayers>,
+) {
+    let music_volume = settings.volume(VolumeSetting::Music);
+    let mut applied_volumes = vec![];
+    for (index, weight) in music_layers.weights.iter().enumerate() {
+        let volume = weight * music_volume;
+        match music_layers.applied_volumes.get(index) {
+            Some(applied) if (applied - volume).abs() <= 0.005 => applied_volumes.push(*applied),
+            _ => {
+                audio.set_volume_in_channel(volume, &music_layers.channels[index]);
+                applied_volumes.push(volume);
+            }
+        }
+    }
+    music_layers.applied_volumes = applied_volumes;
+}
+
+/// Restarts all layers when one of them changed on disk, so they stay in sync
+fn reload_layers(
+    mut events: EventReader<AssetEvent<AudioSource>>,
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+    mut music_layers: ResMut<MusicLayers>,
+) {
+    let modified = events.iter().any(|event| match event {
+        AssetEvent::Modified { handle } => LAYERS
+            .iter()
+            .any(|layer| *handle == layer.source(&audio_assets)),
+        _ => false,
+    });
+    if !modified {
+        return;
+    }
+    for (layer, channel) in LAYERS.iter().zip(music_layers.channels.iter()) {
+        audio.stop_channel(channel);
+        audio.play_looped_in_channel(layer.source(&audio_assets), channel);
+    }
+    // the volumes are sent again by apply_layer_volumes
+    music_layers.applied_volumes = vec![];
+}
+
+fn pause_music(audio: Res<Audio>, music_layers: Res<MusicLayers>) {
+    for channel in music_layers.channels.iter() {
+        audio.pause_channel(channel);
+    }
+}
+
+fn resume_music(audio: Res<Audio>, music_layers: Res<MusicLayers>) {
+    for channel in music_layers.channels.iter() {
+        audio.resume_channel(channel);
+    }
+}
+
+fn stop_music(audio: Res<Audio>, music_layers: Res<MusicLayers>) {
+    for channel in music_layers.channels.iter() {
+        audio.stop_channel(channel);
+    }
+}
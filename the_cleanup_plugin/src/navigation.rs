@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+
+pub struct NavigationPlugin;
+
+#[derive(SystemLabel, Eq, PartialEq, Hash, Clone, Debug)]
+pub enum NavigationSystemLabels {
+    MoveFocus,
+    ActivateButton,
+}
+
+impl Plugin for NavigationPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<Focus>()
+            .add_event::<ButtonActivated>()
+            .add_system(move_focus.system().label(NavigationSystemLabels::MoveFocus))
+            .add_system(
+                activate_focused_button
+                    .system()
+                    .label(NavigationSystemLabels::ActivateButton)
+                    .after(NavigationSystemLabels::MoveFocus),
+            );
+    }
+}
+
+/// The button that is activated by enter, space or the south gamepad button
+#[derive(Default)]
+pub struct Focus(pub Option<Entity>);
+
+/// The focused button was activated with the keyboard or a gamepad
+pub struct ButtonActivated(pub Entity);
+
+enum FocusMove {
+    Previous,
+    Next,
+}
+
+fn read_focus_move(
+    keyboard_input: &Input<KeyCode>,
+    gamepad_input: &Input<GamepadButton>,
+) -> Option<FocusMove> {
+    let gamepad_pressed = |button_type: GamepadButtonType| {
+        gamepad_input
+            .get_just_pressed()
+            .any(|button| button.1 == button_type)
+    };
+    if keyboard_input.just_pressed(KeyCode::Up)
+        || keyboard_input.just_pressed(KeyCode::W)
+        || gamepad_pressed(GamepadButtonType::DPadUp)
+    {
+        Some(FocusMove::Previous)
+    } else if keyboard_input.just_pressed(KeyCode::Down)
+        || keyboard_input.just_pressed(KeyCode::S)
+        || keyboard_input.just_pressed(KeyCode::Tab)
+        || gamepad_pressed(GamepadButtonType::DPadDown)
+    {
+        Some(FocusMove::Next)
+    } else {
+        None
+    }
+}
+
+fn move_focus(
+    mut focus: ResMut<Focus>,
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    buttons: Query<(Entity, &GlobalTransform), With<Button>>,
+    hovered: Query<(Entity, &Interaction), (Changed<Interaction>, With<Button>)>,
+) {
+    // the focused button is gone once its screen was left
+    let focused = focus.0.filter(|focused| buttons.get(*focused).is_ok());
+    let mut target = hovered
+        .iter()
+        .find(|(_, interaction)| **interaction == Interaction::Hovered)
+        .map(|(entity, _)| entity);
+
+    if target.is_none() {
+        // top to bottom, left to right
+        let mut ordered: Vec<(Entity, Vec3)> = buttons
+            .iter()
+            .map(|(entity, transform)| (entity, transform.translation))
+            .collect();
+        ordered.sort_by(|(_, a), (_, b)| {
+            b.y.partial_cmp(&a.y)
+                .unwrap()
+                .then(a.x.partial_cmp(&b.x).unwrap())
+        });
+        let position =
+            focused.and_then(|focused| ordered.iter().position(|(entity, _)| *entity == focused));
+        target = match (read_focus_move(&keyboard_input, &gamepad_input), position) {
+            (Some(_), None) => ordered.first().map(|(entity, _)| *entity),
+            (None, None) => None,
+            (Some(FocusMove::Previous), Some(position)) => ordered
+                .get((position + ordered.len() - 1) % ordered.len())
+                .map(|(entity, _)| *entity),
+            (Some(FocusMove::Next), Some(position)) => ordered
+                .get((position + 1) % ordered.len())
+                .map(|(entity, _)| *entity),
+            (None, Some(_)) => None,
+        };
+    }
+
+    if target.is_some() || focused.is_none() {
+        focus.0 = target;
+    }
+}
+
+fn activate_focused_button(
+    focus: Res<Focus>,
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    mut activated: EventWriter<ButtonActivated>,
+    buttons: Query<(), With<Button>>,
+) {
+    let activate = keyboard_input.just_pressed(KeyCode::Return)
+        || keyboard_input.just_pressed(KeyCode::Space)
+        || gamepad_input
+            .get_just_pressed()
+            .any(|button| button.1 == GamepadButtonType::South);
+    if !activate {
+        return;
+    }
+    if let Some(entity) = focus.0 {
+        if buttons.get(entity).is_ok() {
+            activated.send(ButtonActivated(entity));
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use crate::daily::DailyChallenge;
+use crate::locale::Locale;
+use crate::map::MapSeed;
+use crate::menu::GameSetup;
+use crate::rules::{GameMode, GameRules};
+use crate::theme::UiTheme;
+use crate::widgets::{
+    spawn_button, spawn_label, spawn_screen, ButtonActionPlugin, ButtonMaterials, LabelStyle,
+};
+use crate::GameState;
+use bevy::prelude::*;
+use rand::random;
+
+/// Enough digits for any u64
+const MAX_SEED_DIGITS: usize = 20;
+
+pub struct NewGamePlugin;
+
+impl Plugin for NewGamePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_plugin(ButtonActionPlugin::<NewGameAction>::default())
+            .init_resource::<SeedInput>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::NewGame).with_system(setup_new_game.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::NewGame)
+                    .with_system(type_seed.system())
+                    .with_system(handle_actions.system())
+                    .with_system(update_labels.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::NewGame).with_system(remove_new_game.system()),
+            );
+    }
+}
+
+/// Digits of the seed typed by the player. An empty input starts with a random seed.
+#[derive(Default)]
+pub struct SeedInput(pub String);
+
+impl SeedInput {
+    fn seed(&self) -> Option<u64> {
+        self.0.parse().ok()
+    }
+
+    fn label(&self, locale: &Locale) -> String {
+        if self.0.is_empty() {
+            locale.get("new-game-seed-random")
+        } else {
+            locale.format("new-game-seed", &[("seed", self.0.clone())])
+        }
+    }
+}
+
+struct NewGameUi;
+
+struct ModeLabel;
+
+struct SeedLabel;
+
+#[derive(Clone, Copy)]
+enum NewGameAction {
+    CycleMode,
+    RandomSeed,
+    Start,
+    Daily,
+    DailyHistory,
+    Back,
+}
+
+fn mode_text(mode: &GameMode, locale: &Locale) -> String {
+    locale.format("new-game-mode", &[("mode", locale.get(mode.name_key()))])
+}
+
+fn setup_new_game(
+    mut commands: Commands,
+    theme: Res<UiTheme>,
+    locale: Res<Locale>,
+    button_materials: Res<ButtonMaterials>,
+    mode: Res<GameMode>,
+    seed_input: Res<SeedInput>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands
+        .spawn_bundle(UiCameraBundle::default())
+        .insert(NewGameUi);
+    spawn_screen(
+        &mut commands,
+        &mut color_materials,
+        theme.palette.background,
+    )
+    .insert(NewGameUi)
+    .with_children(|parent| {
+        spawn_label(
+            parent,
+            &theme,
+            locale.get("new-game-title"),
+            LabelStyle::Title,
+        );
+        spawn_label(
+            parent,
+            &theme,
+            mode_text(&mode, &locale),
+            LabelStyle::Subtitle,
+        )
+        .insert(ModeLabel);
+        spawn_label(
+            parent,
+            &theme,
+            seed_input.label(&locale),
+            LabelStyle::Subtitle,
+        )
+        .insert(SeedLabel);
+        for (label, action) in vec![
+            ("new-game-change-mode", NewGameAction::CycleMode),
+            ("new-game-random-seed", NewGameAction::RandomSeed),
+            ("new-game-start", NewGameAction::Start),
+            ("new-game-daily", NewGameAction::Daily),
+            ("new-game-daily-history", NewGameAction::DailyHistory),
+            ("back", NewGameAction::Back),
+        ] {
+            spawn_button(
+                parent,
+                &button_materials,
+                &theme,
+                locale.get(label),
+                360.,
+                action,
+            );
+        }
+    });
+}
+
+fn type_seed(
+    mut seed_input: ResMut<SeedInput>,
+    mut characters: EventReader<ReceivedCharacter>,
+    keyboard_input: Res<Input<KeyCode>>,
+) {
+    for event in characters.iter() {
+        if event.char.is_ascii_digit() && seed_input.0.len() < MAX_SEED_DIGITS {
+            seed_input.0.push(event.char);
+            if seed_input.seed().is_none() {
+                // larger than any u64
+                seed_input.0.pop();
+            }
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        seed_input.0.pop();
+    }
+}
+
+fn update_labels(
+    mode: Res<GameMode>,
+    seed_input: Res<SeedInput>,
+    locale: Res<Locale>,
+    mut mode_label: Query<&mut Text, (With<ModeLabel>, Without<SeedLabel>)>,
+    mut seed_label: Query<&mut Text, (With<SeedLabel>, Without<ModeLabel>)>,
+) {
+    if mode.is_changed() {
+        for mut text in mode_label.iter_mut() {
+            text.sections.first_mut().unwrap().value = mode_text(&mode, &locale);
+        }
+    }
+    if seed_input.is_changed() {
+        for mut text in seed_label.iter_mut() {
+            text.sections.first_mut().unwrap().value = seed_input.label(&locale);
+        }
+    }
+}
+
+fn handle_actions(
+    mut actions: EventReader<NewGameAction>,
+    mut state: ResMut<State<GameState>>,
+    mut seed_input: ResMut<SeedInput>,
+    mut mode: ResMut<GameMode>,
+    mut rules: ResMut<GameRules>,
+    mut seed: ResMut<MapSeed>,
+    mut daily: ResMut<DailyChallenge>,
+) {
+    for action in actions.iter() {
+        match action {
+            NewGameAction::CycleMode => *mode = mode.next(),
+            NewGameAction::RandomSeed => seed_input.0.clear(),
+            NewGameAction::Start => {
+                GameSetup {
+                    mode: *mode,
+                    seed: seed_input.seed().unwrap_or_else(random),
+                    daily: None,
+                }
+                .apply(&mut mode, &mut rules, &mut seed, &mut daily);
+                state.set(GameState::Playing).unwrap();
+            }
+            NewGameAction::Daily => {
+                let day = DailyChallenge::today();
+                GameSetup {
+                    mode: GameMode::Classic,
+                    seed: DailyChallenge::seed(day),
+                    daily: Some(day),
+                }
+                .apply(&mut mode, &mut rules, &mut seed, &mut daily);
+                state.set(GameState::Playing).unwrap();
+            }
+            NewGameAction::DailyHistory => state.set(GameState::DailyHistory).unwrap(),
+            NewGameAction::Back => state.set(GameState::Menu).unwrap(),
+        }
+    }
+}
+
+fn remove_new_game(mut commands: Commands, query: Query<Entity, With<NewGameUi>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
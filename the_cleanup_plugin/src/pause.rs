@@ -0,0 +1,119 @@
+use crate::digger::DiggerState;
+use crate::locale::Locale;
+use crate::rules::RulesSystemLabels;
+use crate::theme::UiTheme;
+use crate::widgets::{
+    spawn_button, spawn_label, spawn_screen, ButtonActionPlugin, ButtonMaterials, LabelStyle,
+};
+use crate::GameState;
+use bevy::prelude::*;
+use bevy::window::WindowFocused;
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_plugin(ButtonActionPlugin::<PauseAction>::default())
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(pause_game.system().after(RulesSystemLabels::EvaluateRules)),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::Paused).with_system(setup_pause_menu.system()),
+            )
+            // the settings screen is pushed on top of the pause menu
+            .add_system_set(
+                SystemSet::on_resume(GameState::Paused).with_system(setup_pause_menu.system()),
+            )
+            .add_system_set(
+                SystemSet::on_pause(GameState::Paused).with_system(remove_pause_menu.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Paused)
+                    .with_system(resume_game.system())
+                    .with_system(handle_actions.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Paused).with_system(remove_pause_menu.system()),
+            );
+    }
+}
+
+struct PauseMenu;
+
+#[derive(Clone, Copy)]
+enum PauseAction {
+    Resume,
+    Settings,
+    Restart,
+    Quit,
+}
+
+fn pause_game(
+    mut state: ResMut<State<GameState>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut window_focused: EventReader<WindowFocused>,
+    digger_state: Res<DiggerState>,
+) {
+    let lost_focus = window_focused.iter().any(|event| !event.focused);
+    if digger_state.dead {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::Escape) || lost_focus {
+        state.push(GameState::Paused).unwrap();
+    }
+}
+
+fn resume_game(mut state: ResMut<State<GameState>>, mut keyboard_input: ResMut<Input<KeyCode>>) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        // the key would otherwise pause the game again in the same frame
+        keyboard_input.reset(KeyCode::Escape);
+        state.pop().unwrap();
+    }
+}
+
+fn setup_pause_menu(
+    mut commands: Commands,
+    theme: Res<UiTheme>,
+    locale: Res<Locale>,
+    button_materials: Res<ButtonMaterials>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+) {
+    spawn_screen(&mut commands, &mut color_materials, theme.palette.overlay)
+        .insert(PauseMenu)
+        .with_children(|parent| {
+            spawn_label(parent, &theme, locale.get("pause-title"), LabelStyle::Title);
+            for (label, action) in vec![
+                ("pause-resume", PauseAction::Resume),
+                ("pause-settings", PauseAction::Settings),
+                ("pause-restart", PauseAction::Restart),
+                ("pause-quit", PauseAction::Quit),
+            ] {
+                spawn_button(
+                    parent,
+                    &button_materials,
+                    &theme,
+                    locale.get(label),
+                    240.,
+                    action,
+                );
+            }
+        });
+}
+
+fn handle_actions(mut actions: EventReader<PauseAction>, mut state: ResMut<State<GameState>>) {
+    for action in actions.iter() {
+        match action {
+            PauseAction::Resume => state.pop().unwrap(),
+            PauseAction::Settings => state.push(GameState::Settings).unwrap(),
+            PauseAction::Restart => state.replace(GameState::Restart).unwrap(),
+            PauseAction::Quit => state.replace(GameState::Menu).unwrap(),
+        }
+    }
+}
+
+fn remove_pause_menu(mut commands: Commands, query: Query<Entity, With<PauseMenu>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
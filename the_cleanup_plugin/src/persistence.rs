@@ -0,0 +1,54 @@
+use bevy::log::warn;
+use ron::ser::PrettyConfig;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// Saves live next to the executable, or in the plugin directory for debug builds
+fn save_directory() -> PathBuf {
+    if cfg!(debug_assertions) {
+        return PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("saves");
+    }
+    env::current_exe()
+        .ok()
+        .and_then(|executable| executable.parent().map(|directory| directory.to_path_buf()))
+        .unwrap_or_default()
+        .join("saves")
+}
+
+/// Load a saved value or fall back to its default if there is none or it can't be read
+pub fn load<T: DeserializeOwned + Default>(file_name: &str) -> T {
+    let path = save_directory().join(file_name);
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(_) => return T::default(),
+    };
+    match ron::de::from_str(&content) {
+        Ok(value) => value,
+        Err(error) => {
+            warn!("Failed to parse {:?}: {}", path, error);
+            T::default()
+        }
+    }
+}
+
+pub fn save<T: Serialize>(file_name: &str, value: &T) {
+    let directory = save_directory();
+    if let Err(error) = fs::create_dir_all(&directory) {
+        warn!("Failed to create save directory {:?}: {}", directory, error);
+        return;
+    }
+    let path = directory.join(file_name);
+    let content = match ron::ser::to_string_pretty(value, PrettyConfig::new()) {
+        Ok(content) => content,
+        Err(error) => {
+            warn!("Failed to serialize {:?}: {}", path, error);
+            return;
+        }
+    };
+    if let Err(error) = fs::write(&path, content) {
+        warn!("Failed to write {:?}: {}", path, error);
+    }
+}
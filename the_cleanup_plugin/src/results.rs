@@ -0,0 +1,176 @@
+use crate::daily::DailyChallenge;
+use crate::digger::DiggerState;
+use crate::locale::Locale;
+use crate::map::{MapSeed, Tile};
+use crate::rules::{FailureCondition, GameLost, GameWon, RunTimer};
+use crate::statistics::{tile_counts, LastRun, RunStatistics, StatisticsSystemLabels};
+use crate::theme::UiTheme;
+use crate::widgets::{
+    spawn_button, spawn_label, spawn_screen, ButtonActionPlugin, ButtonMaterials, LabelStyle,
+};
+use crate::GameState;
+use bevy::prelude::*;
+use rand::random;
+use std::collections::HashMap;
+
+pub struct ResultsPlugin;
+
+impl Plugin for ResultsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_plugin(ButtonActionPlugin::<ResultsAction>::default())
+            .init_resource::<RunSummary>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(
+                    show_results
+                        .system()
+                        .after(StatisticsSystemLabels::FinishRun),
+                ),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::Results).with_system(setup_results.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Results).with_system(handle_actions.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Results).with_system(remove_results.system()),
+            );
+    }
+}
+
+/// Everything shown on the results screen, captured when the run ended
+#[derive(Default)]
+pub struct RunSummary {
+    pub cause: Option<FailureCondition>,
+    pub time: f32,
+    pub money_earned: f32,
+    pub tiles_mined: HashMap<Tile, usize>,
+    pub fuel_consumed: f32,
+    pub max_depth: usize,
+    pub score: u32,
+    pub rank: Option<usize>,
+}
+
+fn show_results(
+    mut game_won: EventReader<GameWon>,
+    mut game_lost: EventReader<GameLost>,
+    mut state: ResMut<State<GameState>>,
+    digger_state: Res<DiggerState>,
+    run_timer: Res<RunTimer>,
+    run_statistics: Res<RunStatistics>,
+    last_run: Res<LastRun>,
+    mut summary: ResMut<RunSummary>,
+) {
+    let won = game_won.iter().count() > 0;
+    let cause = game_lost.iter().next().map(|lost| lost.cause.clone());
+    if !won && cause.is_none() {
+        return;
+    }
+    *summary = RunSummary {
+        cause,
+        time: run_timer.elapsed,
+        money_earned: digger_state.money_earned,
+        tiles_mined: run_statistics.tiles_mined.clone(),
+        fuel_consumed: digger_state.fuel_consumed,
+        max_depth: digger_state.max_depth,
+        score: last_run.score,
+        rank: last_run.rank,
+    };
+    state.set(GameState::Results).unwrap();
+}
+
+struct ResultsUi;
+
+#[derive(Clone, Copy)]
+enum ResultsAction {
+    SameSeed,
+    NewSeed,
+    Menu,
+}
+
+fn setup_results(
+    mut commands: Commands,
+    theme: Res<UiTheme>,
+    locale: Res<Locale>,
+    summary: Res<RunSummary>,
+    button_materials: Res<ButtonMaterials>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let title = locale.get(match &summary.cause {
+        None => "results-won",
+        Some(FailureCondition::OutOfFuel) => "results-out-of-fuel",
+        Some(FailureCondition::TimeLimit(_)) => "results-out-of-time",
+    });
+    let score = ("score", locale.number(summary.score, 0));
+    let lines = vec![
+        locale.format("results-time", &[("time", locale.number(summary.time, 1))]),
+        locale.format(
+            "results-money",
+            &[("money", locale.money(summary.money_earned))],
+        ),
+        locale.format(
+            "results-fuel",
+            &[("fuel", locale.number(summary.fuel_consumed, 1))],
+        ),
+        locale.format("results-depth", &[("depth", summary.max_depth.to_string())]),
+        tile_counts(&summary.tiles_mined, &locale),
+        match summary.rank {
+            Some(rank) => locale.format("results-score-rank", &[score, ("rank", rank.to_string())]),
+            None => locale.format("results-score", &[score]),
+        },
+    ];
+    commands
+        .spawn_bundle(UiCameraBundle::default())
+        .insert(ResultsUi);
+    spawn_screen(
+        &mut commands,
+        &mut color_materials,
+        theme.palette.background,
+    )
+    .insert(ResultsUi)
+    .with_children(|parent| {
+        spawn_label(parent, &theme, title, LabelStyle::Title);
+        for line in lines {
+            spawn_label(parent, &theme, line, LabelStyle::Body);
+        }
+        for (label, action) in vec![
+            ("results-same-seed", ResultsAction::SameSeed),
+            ("results-new-seed", ResultsAction::NewSeed),
+            ("results-menu", ResultsAction::Menu),
+        ] {
+            spawn_button(
+                parent,
+                &button_materials,
+                &theme,
+                locale.get(label),
+                360.,
+                action,
+            );
+        }
+    });
+}
+
+fn handle_actions(
+    mut actions: EventReader<ResultsAction>,
+    mut state: ResMut<State<GameState>>,
+    mut seed: ResMut<MapSeed>,
+    mut daily: ResMut<DailyChallenge>,
+) {
+    for action in actions.iter() {
+        match action {
+            ResultsAction::SameSeed => state.set(GameState::Playing).unwrap(),
+            ResultsAction::NewSeed => {
+                seed.0 = random();
+                daily.active = false;
+                state.set(GameState::Playing).unwrap();
+            }
+            ResultsAction::Menu => state.set(GameState::Menu).unwrap(),
+        }
+    }
+}
+
+fn remove_results(mut commands: Commands, query: Query<Entity, With<ResultsUi>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,245 @@
+use crate::digger::{DiggerState, DiggerSystemLabels, WasteCollected};
+use crate::GameState;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct RulesPlugin;
+
+/// Seconds a time attack run may take at most
+pub const TIME_ATTACK_LIMIT: f32 = 600.;
+
+#[derive(SystemLabel, Eq, PartialEq, Hash, Clone, Debug)]
+pub enum RulesSystemLabels {
+    TickRunTimer,
+    EvaluateRules,
+}
+
+impl Plugin for RulesPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<GameMode>()
+            .init_resource::<GameRules>()
+            .init_resource::<RunTimer>()
+            .add_event::<GameWon>()
+            .add_event::<GameLost>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(reset_run_timer.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(
+                        tick_run_timer
+                            .system()
+                            .label(RulesSystemLabels::TickRunTimer),
+                    )
+                    .with_system(
+                        evaluate_rules
+                            .system()
+                            .label(RulesSystemLabels::EvaluateRules)
+                            .after(RulesSystemLabels::TickRunTimer)
+                            .after(DiggerSystemLabels::Dig)
+                            .after(DiggerSystemLabels::LooseFuel),
+                    )
+                    .with_system(
+                        record_splits
+                            .system()
+                            .after(RulesSystemLabels::TickRunTimer),
+                    ),
+            );
+    }
+}
+
+/// Something the player has to achieve. A run is won once all objectives are met.
+#[derive(Clone, Debug)]
+pub enum Objective {
+    CollectWaste(usize),
+}
+
+/// Ends the run as lost as soon as it is met
+#[derive(Clone, Debug)]
+pub enum FailureCondition {
+    OutOfFuel,
+    /// Seconds the player has to complete all objectives
+    TimeLimit(f32),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum GameMode {
+    /// Collect all the waste before running out of fuel
+    Classic,
+    /// Collect all the waste as fast as possible
+    TimeAttack,
+    /// Dig as deep as possible in a much deeper map, scoring by depth and money
+    Endless,
+    /// Collect all the waste without ever losing fuel
+    Zen,
+}
+
+impl Default for GameMode {
+    fn default() -> Self {
+        GameMode::Classic
+    }
+}
+
+impl GameMode {
+    pub fn next(&self) -> GameMode {
+        match self {
+            GameMode::Classic => GameMode::TimeAttack,
+            GameMode::TimeAttack => GameMode::Endless,
+            GameMode::Endless => GameMode::Zen,
+            GameMode::Zen => GameMode::Classic,
+        }
+    }
+
+    /// Key of the mode name in the locale files
+    pub fn name_key(&self) -> &'static str {
+        match self {
+            GameMode::Classic => "mode-classic",
+            GameMode::TimeAttack => "mode-time-attack",
+            GameMode::Endless => "mode-endless",
+            GameMode::Zen => "mode-zen",
+        }
+    }
+}
+
+pub struct GameRules {
+    pub objectives: Vec<Objective>,
+    pub failure_conditions: Vec<FailureCondition>,
+    /// Fuel lost per second
+    pub fuel_rate: f32,
+    /// Number of tile rows in the generated map
+    pub map_depth: usize,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        GameRules::for_mode(&GameMode::Classic)
+    }
+}
+
+impl GameRules {
+    pub fn for_mode(mode: &GameMode) -> Self {
+        match mode {
+            GameMode::Classic => GameRules {
+                objectives: vec![Objective::CollectWaste(10)],
+                failure_conditions: vec![FailureCondition::OutOfFuel],
+                fuel_rate: 0.5,
+                map_depth: 100,
+            },
+            GameMode::TimeAttack => GameRules {
+                objectives: vec![Objective::CollectWaste(10)],
+                failure_conditions: vec![
+                    FailureCondition::OutOfFuel,
+                    FailureCondition::TimeLimit(TIME_ATTACK_LIMIT),
+                ],
+                fuel_rate: 0.5,
+                map_depth: 100,
+            },
+            GameMode::Endless => GameRules {
+                objectives: vec![],
+                failure_conditions: vec![FailureCondition::OutOfFuel],
+                fuel_rate: 0.5,
+                map_depth: 300,
+            },
+            GameMode::Zen => GameRules {
+                objectives: vec![Objective::CollectWaste(10)],
+                failure_conditions: vec![],
+                fuel_rate: 0.,
+                map_depth: 100,
+            },
+        }
+    }
+
+    pub fn waste_target(&self) -> Option<usize> {
+        self.objectives
+            .iter()
+            .find_map(|objective| match objective {
+                &Objective::CollectWaste(amount) => Some(amount),
+            })
+    }
+}
+
+impl Objective {
+    fn is_met(&self, digger_state: &DiggerState) -> bool {
+        match self {
+            &Objective::CollectWaste(amount) => digger_state.waste >= amount,
+        }
+    }
+}
+
+impl FailureCondition {
+    fn is_met(&self, digger_state: &DiggerState, run_timer: &RunTimer) -> bool {
+        match self {
+            &FailureCondition::OutOfFuel => digger_state.fuel <= 0.,
+            &FailureCondition::TimeLimit(limit) => run_timer.elapsed >= limit,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct RunTimer {
+    pub elapsed: f32,
+    /// Run time at which each piece of waste was collected
+    pub splits: Vec<f32>,
+}
+
+pub struct GameWon;
+
+pub struct GameLost {
+    pub cause: FailureCondition,
+}
+
+fn reset_run_timer(mut run_timer: ResMut<RunTimer>) {
+    *run_timer = RunTimer::default();
+}
+
+fn tick_run_timer(
+    time: Res<Time>,
+    digger_state: Res<DiggerState>,
+    mut run_timer: ResMut<RunTimer>,
+) {
+    if digger_state.dead {
+        return;
+    }
+    run_timer.elapsed += time.delta_seconds();
+}
+
+fn record_splits(mut events: EventReader<WasteCollected>, mut run_timer: ResMut<RunTimer>) {
+    for _event in events.iter() {
+        let elapsed = run_timer.elapsed;
+        run_timer.splits.push(elapsed);
+    }
+}
+
+fn evaluate_rules(
+    rules: Res<GameRules>,
+    run_timer: Res<RunTimer>,
+    mut digger_state: ResMut<DiggerState>,
+    mut game_won: EventWriter<GameWon>,
+    mut game_lost: EventWriter<GameLost>,
+) {
+    if digger_state.dead {
+        return;
+    }
+    if let Some(failure) = rules
+        .failure_conditions
+        .iter()
+        .find(|condition| condition.is_met(&digger_state, &run_timer))
+    {
+        digger_state.dead = true;
+        digger_state.mining_target = None;
+        game_lost.send(GameLost {
+            cause: failure.clone(),
+        });
+        return;
+    }
+    if !rules.objectives.is_empty()
+        && rules
+            .objectives
+            .iter()
+            .all(|objective| objective.is_met(&digger_state))
+    {
+        digger_state.dead = true;
+        digger_state.mining_target = None;
+        game_won.send(GameWon);
+    }
+}
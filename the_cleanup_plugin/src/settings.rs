@@ -0,0 +1,651 @@
+use crate::locale::{Locale, LANGUAGES};
+use crate::persistence;
+use crate::theme::{UiTheme, THEMES};
+use crate::widgets::{
+    spawn_button, spawn_label, spawn_screen, ButtonActionPlugin, ButtonMaterials, LabelStyle,
+};
+use crate::GameState;
+use bevy::ecs::query::ChangeTrackers;
+use bevy::prelude::*;
+use bevy::window::WindowMode;
+use serde::{Deserialize, Serialize};
+
+const SETTINGS_FILE: &str = "settings.ron";
+const RESOLUTIONS: [(f32, f32); 5] = [
+    (800., 600.),
+    (1024., 768.),
+    (1280., 720.),
+    (1600., 900.),
+    (1920., 1080.),
+];
+const VOLUMES: [f32; 11] = [0., 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 1.];
+const UI_SCALES: [f32; 4] = [0.75, 1., 1.25, 1.5];
+const PIXELS_PER_TILE: [f32; 4] = [24., 32., 48., 64.];
+/// Window height the ui layout is made for; taller windows scale the ui up
+const REFERENCE_HEIGHT: f32 = 600.;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.world().contains_resource::<Settings>() {
+            app.insert_resource(Settings::load());
+        }
+        app.add_plugin(ButtonActionPlugin::<SettingsAction>::default())
+            .add_system(apply_window_settings.system())
+            .add_system(scale_ui.system())
+            .add_system_set(
+                SystemSet::on_enter(GameState::Settings).with_system(setup_settings.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Settings)
+                    .with_system(handle_actions.system())
+                    .with_system(update_labels.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Settings).with_system(remove_settings.system()),
+            );
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum ControlScheme {
+    Wasd,
+    Arrows,
+}
+
+pub struct ControlKeys {
+    pub up: KeyCode,
+    pub left: KeyCode,
+    pub down: KeyCode,
+    pub right: KeyCode,
+    pub upgrade: KeyCode,
+}
+
+impl ControlScheme {
+    pub fn keys(&self) -> ControlKeys {
+        match self {
+            ControlScheme::Wasd => ControlKeys {
+                up: KeyCode::W,
+                left: KeyCode::A,
+                down: KeyCode::S,
+                right: KeyCode::D,
+                upgrade: KeyCode::E,
+            },
+            ControlScheme::Arrows => ControlKeys {
+                up: KeyCode::Up,
+                left: KeyCode::Left,
+                down: KeyCode::Down,
+                right: KeyCode::Right,
+                upgrade: KeyCode::RShift,
+            },
+        }
+    }
+
+    /// Key of the upgrade key's name in the locale files
+    pub fn upgrade_key_name(&self) -> &'static str {
+        match self {
+            ControlScheme::Wasd => "key-e",
+            ControlScheme::Arrows => "key-right-shift",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Settings {
+    pub resolution: (f32, f32),
+    pub fullscreen: bool,
+    pub vsync: bool,
+    /// Only applied at startup
+    pub msaa_samples: u32,
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub ambience_volume: f32,
+    pub ui_volume: f32,
+    /// Silences every bus without touching the volumes
+    pub muted: bool,
+    pub ui_scale: f32,
+    /// Size of a map tile on screen before zooming
+    pub pixels_per_tile: f32,
+    /// Key of the ui theme in the `themes` of the asset manifest
+    pub theme: String,
+    /// Key of the locale in the `locales` of the asset manifest
+    pub language: String,
+    pub control_scheme: ControlScheme,
+    /// Shares of the tank at which a fuel warning goes off; only set in the settings file
+    pub fuel_warnings: Vec<f32>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            resolution: (800., 600.),
+            fullscreen: false,
+            vsync: true,
+            msaa_samples: 4,
+            master_volume: 1.,
+            music_volume: 0.5,
+            sfx_volume: 1.,
+            ambience_volume: 0.7,
+            ui_volume: 0.7,
+            muted: false,
+            ui_scale: 1.,
+            pixels_per_tile: 32.,
+            theme: "default".to_owned(),
+            language: "en".to_owned(),
+            control_scheme: ControlScheme::Wasd,
+            fuel_warnings: vec![0.5, 0.25, 0.1],
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        persistence::load(SETTINGS_FILE)
+    }
+
+    pub fn save(&self) {
+        persistence::save(SETTINGS_FILE, self);
+    }
+
+    pub fn window_descriptor(&self) -> WindowDescriptor {
+        WindowDescriptor {
+            width: self.resolution.0,
+            height: self.resolution.1,
+            vsync: self.vsync,
+            mode: self.window_mode(),
+            title: "The Cleanup".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn window_mode(&self) -> WindowMode {
+        if self.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        }
+    }
+
+    /// Volume including the master volume
+    pub fn volume(&self, setting: VolumeSetting) -> f32 {
+        if self.muted {
+            return 0.;
+        }
+        self.master_volume * self.own_volume(setting)
+    }
+
+    /// Volume without the master volume, as shown in the settings
+    fn own_volume(&self, setting: VolumeSetting) -> f32 {
+        match setting {
+            VolumeSetting::Music => self.music_volume,
+            VolumeSetting::Sfx => self.sfx_volume,
+            VolumeSetting::Ambience => self.ambience_volume,
+            VolumeSetting::Ui => self.ui_volume,
+        }
+    }
+
+    fn own_volume_mut(&mut self, setting: VolumeSetting) -> &mut f32 {
+        match setting {
+            VolumeSetting::Music => &mut self.music_volume,
+            VolumeSetting::Sfx => &mut self.sfx_volume,
+            VolumeSetting::Ambience => &mut self.ambience_volume,
+            VolumeSetting::Ui => &mut self.ui_volume,
+        }
+    }
+}
+
+/// Volumes that can be set on their own; the master volume scales all of them
+///
+/// Every setting but music is the volume of an [`AudioBus`](crate::audio::AudioBus).
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum VolumeSetting {
+    Music,
+    Sfx,
+    Ambience,
+    Ui,
+}
+
+impl VolumeSetting {
+    /// Key of the setting's label in the locale files
+    fn label_key(&self) -> &'static str {
+        match self {
+            VolumeSetting::Music => "settings-music-volume",
+            VolumeSetting::Sfx => "settings-sfx-volume",
+            VolumeSetting::Ambience => "settings-ambience-volume",
+            VolumeSetting::Ui => "settings-ui-volume",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum SettingOption {
+    Resolution,
+    Fullscreen,
+    Vsync,
+    Msaa,
+    MasterVolume,
+    Volume(VolumeSetting),
+    Mute,
+    UiScale,
+    PixelsPerTile,
+    Theme,
+    Language,
+    Controls,
+}
+
+/// Display options on the left, audio and controls on the right
+const SETTING_COLUMNS: [&[SettingOption]; 2] = [
+    &[
+        SettingOption::Resolution,
+        SettingOption::Fullscreen,
+        SettingOption::Vsync,
+        SettingOption::Msaa,
+        SettingOption::UiScale,
+        SettingOption::PixelsPerTile,
+        SettingOption::Theme,
+    ],
+    &[
+        SettingOption::MasterVolume,
+        SettingOption::Volume(VolumeSetting::Music),
+        SettingOption::Volume(VolumeSetting::Sfx),
+        SettingOption::Volume(VolumeSetting::Ambience),
+        SettingOption::Volume(VolumeSetting::Ui),
+        SettingOption::Mute,
+        SettingOption::Language,
+        SettingOption::Controls,
+    ],
+];
+
+impl SettingOption {
+    fn label(&self, settings: &Settings, locale: &Locale) -> String {
+        let on_off = |value: bool| locale.get(if value { "on" } else { "off" });
+        let percent = |value: f32| vec![("percent", locale.number(value * 100., 0))];
+        match self {
+            SettingOption::Resolution => locale.format(
+                "settings-resolution",
+                &[
+                    ("width", settings.resolution.0.to_string()),
+                    ("height", settings.resolution.1.to_string()),
+                ],
+            ),
+            SettingOption::Fullscreen => locale.format(
+                "settings-fullscreen",
+                &[("value", on_off(settings.fullscreen))],
+            ),
+            SettingOption::Vsync => {
+                locale.format("settings-vsync", &[("value", on_off(settings.vsync))])
+            }
+            SettingOption::Msaa => locale.format(
+                "settings-msaa",
+                &[("value", on_off(settings.msaa_samples > 1))],
+            ),
+            SettingOption::MasterVolume => {
+                locale.format("settings-master-volume", &percent(settings.master_volume))
+            }
+            SettingOption::Volume(setting) => {
+                locale.format(setting.label_key(), &percent(settings.own_volume(*setting)))
+            }
+            SettingOption::Mute => {
+                locale.format("settings-mute", &[("value", on_off(settings.muted))])
+            }
+            SettingOption::UiScale => {
+                locale.format("settings-ui-scale", &percent(settings.ui_scale))
+            }
+            SettingOption::PixelsPerTile => locale.format(
+                "settings-pixels-per-tile",
+                &[("pixels", settings.pixels_per_tile.to_string())],
+            ),
+            SettingOption::Theme => locale.format(
+                "settings-theme",
+                &[(
+                    "theme",
+                    locale.get(
+                        THEMES
+                            .iter()
+                            .find(|(name, _)| *name == settings.theme)
+                            .map(|(_, key)| *key)
+                            .unwrap_or(THEMES[0].1),
+                    ),
+                )],
+            ),
+            SettingOption::Language => locale.format(
+                "settings-language",
+                &[(
+                    "language",
+                    LANGUAGES
+                        .iter()
+                        .find(|(name, _)| *name == settings.language)
+                        .map(|(_, display_name)| *display_name)
+                        .unwrap_or(LANGUAGES[0].1)
+                        .to_owned(),
+                )],
+            ),
+            SettingOption::Controls => locale.format(
+                "settings-controls",
+                &[(
+                    "controls",
+                    locale.get(match settings.control_scheme {
+                        ControlScheme::Wasd => "controls-wasd",
+                        ControlScheme::Arrows => "controls-arrows",
+                    }),
+                )],
+            ),
+        }
+    }
+
+    fn cycle(&self, settings: &mut Settings) {
+        match self {
+            SettingOption::Resolution => {
+                let index = RESOLUTIONS
+                    .iter()
+                    .position(|resolution| *resolution == settings.resolution)
+                    .map(|index| (index + 1) % RESOLUTIONS.len())
+                    .unwrap_or(0);
+                settings.resolution = RESOLUTIONS[index];
+            }
+            SettingOption::Fullscreen => settings.fullscreen = !settings.fullscreen,
+            SettingOption::Vsync => settings.vsync = !settings.vsync,
+            SettingOption::Msaa => {
+                settings.msaa_samples = if settings.msaa_samples > 1 { 1 } else { 4 }
+            }
+            SettingOption::MasterVolume => {
+                settings.master_volume = next_value(&VOLUMES, settings.master_volume)
+            }
+            SettingOption::Volume(setting) => {
+                let volume = settings.own_volume_mut(*setting);
+                *volume = next_value(&VOLUMES, *volume);
+            }
+            SettingOption::Mute => settings.muted = !settings.muted,
+            SettingOption::UiScale => settings.ui_scale = next_value(&UI_SCALES, settings.ui_scale),
+            SettingOption::PixelsPerTile => {
+                settings.pixels_per_tile = next_value(&PIXELS_PER_TILE, settings.pixels_per_tile)
+            }
+            SettingOption::Theme => {
+                let index = THEMES
+                    .iter()
+                    .position(|(name, _)| *name == settings.theme)
+                    .map(|index| (index + 1) % THEMES.len())
+                    .unwrap_or(0);
+                settings.theme = THEMES[index].0.to_owned();
+            }
+            SettingOption::Language => {
+                let index = LANGUAGES
+                    .iter()
+                    .position(|(name, _)| *name == settings.language)
+                    .map(|index| (index + 1) % LANGUAGES.len())
+                    .unwrap_or(0);
+                settings.language = LANGUAGES[index].0.to_owned();
+            }
+            SettingOption::Controls => {
+                settings.control_scheme = match settings.control_scheme {
+                    ControlScheme::Wasd => ControlScheme::Arrows,
+                    ControlScheme::Arrows => ControlScheme::Wasd,
+                }
+            }
+        }
+    }
+}
+
+/// The value following the first one larger than the current value, wrapping around at the end
+fn next_value(values: &[f32], current: f32) -> f32 {
+    values
+        .iter()
+        .find(|value| **value > current + 0.001)
+        .cloned()
+        .unwrap_or(values[0])
+}
+
+/// `applied_resolution` is the resolution last set from the settings; as long as it stays
+/// the same, changing other settings keeps the size the window was resized to
+fn apply_window_settings(
+    settings: Res<Settings>,
+    mut windows: ResMut<Windows>,
+    mut applied_resolution: Local<Option<(f32, f32)>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    if let Some(window) = windows.get_primary_mut() {
+        let resolution = settings.resolution;
+        if applied_resolution.is_none() {
+            // the window was created with the resolution from the settings
+            *applied_resolution = Some(resolution);
+        }
+        let size = (window.width(), window.height());
+        if *applied_resolution != Some(resolution) && size != resolution {
+            window.set_resolution(resolution.0, resolution.1);
+        }
+        *applied_resolution = Some(resolution);
+        if window.mode() != settings.window_mode() {
+            window.set_mode(settings.window_mode());
+        }
+        if window.vsync() != settings.vsync {
+            window.set_vsync(settings.vsync);
+        }
+    }
+}
+
+/// Unscaled layout of a ui node, kept to rescale it whenever the ui scale changes
+///
+/// Font sizes changed here are scaled and applied to the text right away.
+pub struct UnscaledNode {
+    style: Style,
+    pub font_sizes: Vec<f32>,
+}
+
+/// The ui scale from the settings, applied on top of the scale for the window height
+fn scale_ui(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    windows: Res<Windows>,
+    mut applied_scale: Local<f32>,
+    mut new_nodes: Query<
+        (Entity, &mut Style, Option<&mut Text>),
+        (Added<Node>, Without<UnscaledNode>),
+    >,
+    mut scaled_nodes: Query<(
+        ChangeTrackers<UnscaledNode>,
+        &UnscaledNode,
+        &mut Style,
+        Option<&mut Text>,
+    )>,
+) {
+    let window_scale = windows
+        .get_primary()
+        .map(|window| window.height() / REFERENCE_HEIGHT)
+        .unwrap_or(1.);
+    let scale = settings.ui_scale * window_scale;
+    for (entity, mut style, text) in new_nodes.iter_mut() {
+        let unscaled = UnscaledNode {
+            style: style.clone(),
+            font_sizes: text
+                .as_ref()
+                .map(|text| {
+                    text.sections
+                        .iter()
+                        .map(|section| section.style.font_size)
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+        apply_scale(&unscaled, &mut style, text, scale);
+        commands.entity(entity).insert(unscaled);
+    }
+    let rescale_all = scale != *applied_scale;
+    for (tracker, unscaled, mut style, text) in scaled_nodes.iter_mut() {
+        if rescale_all || tracker.is_changed() {
+            apply_scale(unscaled, &mut style, text, scale);
+        }
+    }
+    *applied_scale = scale;
+}
+
+/// Only pixel values are scaled. Percentages and values that systems changed to another unit
+/// at runtime, like gauge widths and marker positions, are left as they are.
+fn apply_scale(unscaled: &UnscaledNode, style: &mut Style, text: Option<Mut<Text>>, scale: f32) {
+    let scale_value = |unscaled: Val, current: Val| match (unscaled, current) {
+        (Val::Px(pixels), Val::Px(_)) => Val::Px(pixels * scale),
+        (_, current) => current,
+    };
+    let scale_rect = |unscaled: Rect<Val>, current: Rect<Val>| Rect {
+        left: scale_value(unscaled.left, current.left),
+        right: scale_value(unscaled.right, current.right),
+        top: scale_value(unscaled.top, current.top),
+        bottom: scale_value(unscaled.bottom, current.bottom),
+    };
+    let scale_size = |unscaled: Size<Val>, current: Size<Val>| {
+        Size::new(
+            scale_value(unscaled.width, current.width),
+            scale_value(unscaled.height, current.height),
+        )
+    };
+    style.position = scale_rect(unscaled.style.position, style.position);
+    style.margin = scale_rect(unscaled.style.margin, style.margin);
+    style.padding = scale_rect(unscaled.style.padding, style.padding);
+    style.border = scale_rect(unscaled.style.border, style.border);
+    style.size = scale_size(unscaled.style.size, style.size);
+    style.min_size = scale_size(unscaled.style.min_size, style.min_size);
+    style.max_size = scale_size(unscaled.style.max_size, style.max_size);
+    if let Some(mut text) = text {
+        for (section, font_size) in text.sections.iter_mut().zip(unscaled.font_sizes.iter()) {
+            section.style.font_size = font_size * scale;
+        }
+    }
+}
+
+struct SettingsUi;
+
+struct SettingsTitle;
+
+#[derive(Clone, Copy)]
+enum SettingsAction {
+    Cycle(SettingOption),
+    Back,
+}
+
+fn setup_settings(
+    mut commands: Commands,
+    theme: Res<UiTheme>,
+    locale: Res<Locale>,
+    settings: Res<Settings>,
+    state: Res<State<GameState>>,
+    button_materials: Res<ButtonMaterials>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+) {
+    // opened from the pause menu, the ui camera of the paused game is still around
+    if state.inactives().is_empty() {
+        commands
+            .spawn_bundle(UiCameraBundle::default())
+            .insert(SettingsUi);
+    }
+    spawn_screen(&mut commands, &mut color_materials, theme.palette.panel)
+        .insert(SettingsUi)
+        .with_children(|parent| {
+            spawn_label(
+                parent,
+                &theme,
+                locale.get("settings-title"),
+                LabelStyle::Title,
+            )
+            .insert(SettingsTitle);
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::FlexStart,
+                        ..Default::default()
+                    },
+                    material: color_materials.add(Color::NONE.into()),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    for column in SETTING_COLUMNS.iter() {
+                        parent
+                            .spawn_bundle(NodeBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::ColumnReverse,
+                                    ..Default::default()
+                                },
+                                material: color_materials.add(Color::NONE.into()),
+                                ..Default::default()
+                            })
+                            .with_children(|parent| {
+                                for option in column.iter() {
+                                    spawn_button(
+                                        parent,
+                                        &button_materials,
+                                        &theme,
+                                        option.label(&settings, &locale),
+                                        360.,
+                                        SettingsAction::Cycle(*option),
+                                    );
+                                }
+                            });
+                    }
+                });
+            spawn_button(
+                parent,
+                &button_materials,
+                &theme,
+                locale.get("back"),
+                240.,
+                SettingsAction::Back,
+            );
+        });
+}
+
+fn handle_actions(
+    mut actions: EventReader<SettingsAction>,
+    mut settings: ResMut<Settings>,
+    mut state: ResMut<State<GameState>>,
+) {
+    for action in actions.iter() {
+        match action {
+            SettingsAction::Cycle(option) => option.cycle(&mut settings),
+            SettingsAction::Back => {
+                settings.save();
+                if state.inactives().is_empty() {
+                    state.set(GameState::Menu).unwrap();
+                } else {
+                    state.pop().unwrap();
+                }
+            }
+        }
+    }
+}
+
+/// Relabels the screen after an option changed, in the new language if that was the option
+fn update_labels(
+    settings: Res<Settings>,
+    locale: Res<Locale>,
+    buttons: Query<(&SettingsAction, &Children)>,
+    mut title: Query<&mut Text, With<SettingsTitle>>,
+    mut text_query: Query<&mut Text, Without<SettingsTitle>>,
+) {
+    if !settings.is_changed() && !locale.is_changed() {
+        return;
+    }
+    for mut text in title.iter_mut() {
+        text.sections.first_mut().unwrap().value = locale.get("settings-title");
+    }
+    for (action, children) in buttons.iter() {
+        let label = match action {
+            SettingsAction::Cycle(option) => option.label(&settings, &locale),
+            SettingsAction::Back => locale.get("back"),
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections.first_mut().unwrap().value = label.clone();
+            }
+        }
+    }
+}
+
+fn remove_settings(mut commands: Commands, query: Query<Entity, With<SettingsUi>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,360 @@
+use crate::base::FuelBought;
+use crate::digger::{DiggerState, TileMined};
+use crate::locale::Locale;
+use crate::map::Tile;
+use crate::persistence;
+use crate::rules::{GameLost, GameMode, GameWon, RulesSystemLabels, RunTimer};
+use crate::theme::UiTheme;
+use crate::widgets::{
+    spawn_button, spawn_label, spawn_screen, ButtonActionPlugin, ButtonMaterials, LabelStyle,
+};
+use crate::GameState;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const STATISTICS_FILE: &str = "statistics.ron";
+const HIGH_SCORE_ENTRIES: usize = 10;
+/// Older runs are dropped from the run records
+const RUN_RECORDS: usize = 100;
+
+/// Tiles that can be mined in the order they are listed in statistics
+pub const MINED_TILES: [Tile; 6] = [
+    Tile::Stone,
+    Tile::Silver,
+    Tile::Gold,
+    Tile::Diamond,
+    Tile::TankUpgrade,
+    Tile::Waste,
+];
+
+pub struct StatisticsPlugin;
+
+#[derive(SystemLabel, Eq, PartialEq, Hash, Clone, Debug)]
+pub enum StatisticsSystemLabels {
+    FinishRun,
+}
+
+impl Plugin for StatisticsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_plugin(ButtonActionPlugin::<StatisticsAction>::default())
+            .init_resource::<RunStatistics>()
+            .init_resource::<LastRun>()
+            .insert_resource(persistence::load::<StatisticsStore>(STATISTICS_FILE))
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_run.system()))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(count_mined_tiles.system())
+                    .with_system(count_fuel_bought.system())
+                    .with_system(
+                        finish_run
+                            .system()
+                            .label(StatisticsSystemLabels::FinishRun)
+                            .after(RulesSystemLabels::EvaluateRules),
+                    ),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::Statistics).with_system(setup_statistics.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Statistics).with_system(handle_actions.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Statistics).with_system(remove_statistics.system()),
+            );
+    }
+}
+
+/// Statistics of the current run
+#[derive(Default)]
+pub struct RunStatistics {
+    pub tiles_mined: HashMap<Tile, usize>,
+    pub fuel_bought: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HighScore {
+    pub score: u32,
+    pub mode: String,
+    pub won: bool,
+    pub time: f32,
+    pub money: f32,
+    pub depth: usize,
+}
+
+/// Everything worth remembering about one finished run
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RunRecord {
+    pub mode: String,
+    pub won: bool,
+    pub time: f32,
+    pub money: f32,
+    pub depth: usize,
+    pub tiles_mined: HashMap<Tile, usize>,
+    pub fuel_bought: f32,
+}
+
+/// Everything that is kept between runs and sessions
+#[derive(Serialize, Deserialize, Default)]
+pub struct StatisticsStore {
+    pub runs: usize,
+    pub wins: usize,
+    pub deaths: usize,
+    pub time_played: f32,
+    pub money_earned: f32,
+    pub fuel_bought: f32,
+    pub tiles_mined: HashMap<Tile, usize>,
+    pub high_scores: Vec<HighScore>,
+    /// The last finished runs, oldest first; missing in files saved before it was added
+    #[serde(default)]
+    pub run_records: Vec<RunRecord>,
+}
+
+impl StatisticsStore {
+    /// Returns the rank of the score if it made it into the high score table
+    fn submit(&mut self, high_score: HighScore) -> Option<usize> {
+        let rank = self
+            .high_scores
+            .iter()
+            .position(|entry| entry.score < high_score.score)
+            .unwrap_or_else(|| self.high_scores.len());
+        if rank >= HIGH_SCORE_ENTRIES {
+            return None;
+        }
+        self.high_scores.insert(rank, high_score);
+        self.high_scores.truncate(HIGH_SCORE_ENTRIES);
+        Some(rank + 1)
+    }
+
+    fn record(&mut self, run_record: RunRecord) {
+        self.run_records.push(run_record);
+        if self.run_records.len() > RUN_RECORDS {
+            let excess = self.run_records.len() - RUN_RECORDS;
+            self.run_records.drain(..excess);
+        }
+    }
+}
+
+/// Result of the last finished run
+#[derive(Default)]
+pub struct LastRun {
+    pub score: u32,
+    pub rank: Option<usize>,
+}
+
+/// Mined amount of every tile in [`MINED_TILES`] on one line
+pub fn tile_counts(tiles_mined: &HashMap<Tile, usize>, locale: &Locale) -> String {
+    MINED_TILES
+        .iter()
+        .map(|tile| {
+            locale.format(
+                "tile-count",
+                &[
+                    ("tile", locale.get(tile.name_key())),
+                    (
+                        "count",
+                        tiles_mined.get(tile).cloned().unwrap_or(0).to_string(),
+                    ),
+                ],
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("   ")
+}
+
+/// Endless runs score by depth and money. In the other modes waste is worth the most,
+/// and winning a time attack quickly earns a bonus.
+pub fn score(mode: &GameMode, digger_state: &DiggerState, won: bool, time: f32) -> u32 {
+    let depth_and_money = digger_state.max_depth as f32 * 10. + digger_state.money_earned;
+    let score = match mode {
+        GameMode::Endless => depth_and_money,
+        GameMode::TimeAttack if won => {
+            digger_state.waste as f32 * 100. + (600. - time).max(0.) * 10.
+        }
+        _ => digger_state.waste as f32 * 100. + depth_and_money,
+    };
+    score.round() as u32
+}
+
+fn reset_run(mut run_statistics: ResMut<RunStatistics>) {
+    *run_statistics = RunStatistics::default();
+}
+
+fn count_mined_tiles(
+    mut events: EventReader<TileMined>,
+    mut run_statistics: ResMut<RunStatistics>,
+) {
+    for event in events.iter() {
+        *run_statistics
+            .tiles_mined
+            .entry(event.tile.clone())
+            .or_insert(0) += 1;
+    }
+}
+
+fn count_fuel_bought(
+    mut events: EventReader<FuelBought>,
+    mut run_statistics: ResMut<RunStatistics>,
+) {
+    for event in events.iter() {
+        run_statistics.fuel_bought += event.amount;
+    }
+}
+
+fn finish_run(
+    mut game_won: EventReader<GameWon>,
+    mut game_lost: EventReader<GameLost>,
+    digger_state: Res<DiggerState>,
+    run_timer: Res<RunTimer>,
+    mode: Res<GameMode>,
+    run_statistics: Res<RunStatistics>,
+    mut store: ResMut<StatisticsStore>,
+    mut last_run: ResMut<LastRun>,
+) {
+    let won = game_won.iter().count() > 0;
+    let lost = game_lost.iter().count() > 0;
+    if !(won || lost) {
+        return;
+    }
+    store.runs += 1;
+    if won {
+        store.wins += 1;
+    } else {
+        store.deaths += 1;
+    }
+    store.time_played += run_timer.elapsed;
+    store.money_earned += digger_state.money_earned;
+    store.fuel_bought += run_statistics.fuel_bought;
+    for (tile, amount) in run_statistics.tiles_mined.iter() {
+        *store.tiles_mined.entry(tile.clone()).or_insert(0) += amount;
+    }
+
+    store.record(RunRecord {
+        mode: mode.name_key().to_owned(),
+        won,
+        time: run_timer.elapsed,
+        money: digger_state.money_earned,
+        depth: digger_state.max_depth,
+        tiles_mined: run_statistics.tiles_mined.clone(),
+        fuel_bought: run_statistics.fuel_bought,
+    });
+
+    let score = score(&mode, &digger_state, won, run_timer.elapsed);
+    let rank = store.submit(HighScore {
+        score,
+        mode: mode.name_key().to_owned(),
+        won,
+        time: run_timer.elapsed,
+        money: digger_state.money_earned,
+        depth: digger_state.max_depth,
+    });
+    *last_run = LastRun { score, rank };
+    persistence::save(STATISTICS_FILE, &*store);
+}
+
+struct StatisticsUi;
+
+#[derive(Clone, Copy)]
+enum StatisticsAction {
+    Back,
+}
+
+fn setup_statistics(
+    mut commands: Commands,
+    theme: Res<UiTheme>,
+    locale: Res<Locale>,
+    store: Res<StatisticsStore>,
+    button_materials: Res<ButtonMaterials>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let totals = vec![
+        locale.format(
+            "statistics-runs",
+            &[
+                ("runs", store.runs.to_string()),
+                ("wins", store.wins.to_string()),
+                ("deaths", store.deaths.to_string()),
+            ],
+        ),
+        locale.format(
+            "statistics-totals",
+            &[
+                ("time", locale.number(store.time_played, 0)),
+                ("money", locale.money(store.money_earned)),
+                ("fuel", locale.number(store.fuel_bought, 0)),
+            ],
+        ),
+        tile_counts(&store.tiles_mined, &locale),
+    ];
+    let mut high_scores: Vec<String> = store
+        .high_scores
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            locale.format(
+                "statistics-entry",
+                &[
+                    ("rank", (index + 1).to_string()),
+                    ("score", locale.number(entry.score, 0)),
+                    // entries saved before localization contain the mode name itself
+                    ("mode", locale.get(&entry.mode)),
+                    (
+                        "result",
+                        locale.get(if entry.won {
+                            "statistics-won"
+                        } else {
+                            "statistics-lost"
+                        }),
+                    ),
+                    ("time", locale.number(entry.time, 0)),
+                    ("depth", entry.depth.to_string()),
+                ],
+            )
+        })
+        .collect();
+    if high_scores.is_empty() {
+        high_scores.push(locale.get("statistics-empty"));
+    }
+    commands
+        .spawn_bundle(UiCameraBundle::default())
+        .insert(StatisticsUi);
+    spawn_screen(
+        &mut commands,
+        &mut color_materials,
+        theme.palette.background,
+    )
+    .insert(StatisticsUi)
+    .with_children(|parent| {
+        for (title, lines) in vec![
+            ("statistics-title", totals),
+            ("statistics-high-scores", high_scores),
+        ] {
+            spawn_label(parent, &theme, locale.get(title), LabelStyle::Title);
+            for line in lines {
+                spawn_label(parent, &theme, line, LabelStyle::Small);
+            }
+        }
+        spawn_button(
+            parent,
+            &button_materials,
+            &theme,
+            locale.get("back"),
+            240.,
+            StatisticsAction::Back,
+        );
+    });
+}
+
+fn handle_actions(mut actions: EventReader<StatisticsAction>, mut state: ResMut<State<GameState>>) {
+    for action in actions.iter() {
+        match action {
+            StatisticsAction::Back => state.set(GameState::Menu).unwrap(),
+        }
+    }
+}
+
+fn remove_statistics(mut commands: Commands, query: Query<Entity, With<StatisticsUi>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
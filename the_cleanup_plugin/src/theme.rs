@@ -0,0 +1,163 @@
+use crate::loading::ThemeAssets;
+use crate::settings::{Settings, UnscaledNode};
+use crate::widgets::{ButtonMaterials, ThemedText};
+use crate::GameState;
+use bevy::asset::{AssetLoader, AssetPath, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use serde::Deserialize;
+
+/// Keys of the themes in `assets/game.manifest` and the keys of their names in the locale files
+pub const THEMES: [(&str, &str); 2] = [
+    ("default", "theme-default"),
+    ("high_contrast", "theme-high-contrast"),
+];
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_asset::<UiTheme>()
+            .init_asset_loader::<UiThemeLoader>()
+            // the menu needs a theme as soon as loading is done
+            .add_system_set(
+                SystemSet::on_exit(GameState::Loading).with_system(select_theme.system()),
+            )
+            .add_system(select_theme.system())
+            .add_system(apply_theme.system());
+    }
+}
+
+#[derive(Deserialize, Clone, PartialEq)]
+pub struct Palette {
+    pub title: Color,
+    pub text: Color,
+    pub button: Color,
+    pub button_hovered: Color,
+    /// Behind menus and end screens
+    pub background: Color,
+    /// Covers the game while it is paused
+    pub overlay: Color,
+    /// Opaque background of screens that can be opened on top of the game
+    pub panel: Color,
+    /// Filled part of hud gauges
+    pub gauge: Color,
+    /// Filled part of a gauge that is running low
+    pub gauge_low: Color,
+    pub gauge_background: Color,
+}
+
+#[derive(Deserialize, Clone, PartialEq)]
+pub struct FontSizes {
+    pub title: f32,
+    pub hud: f32,
+    pub subtitle: f32,
+    pub button: f32,
+    pub body: f32,
+    pub small: f32,
+}
+
+/// Colours, font and font sizes of the whole ui
+///
+/// The selected theme is also available as resource.
+#[derive(TypeUuid, Clone, PartialEq)]
+#[uuid = "aac1c588-ab4b-403c-959f-8de9b3740246"]
+pub struct UiTheme {
+    pub font: Handle<Font>,
+    pub palette: Palette,
+    pub sizes: FontSizes,
+}
+
+/// Content of a theme file; the font is given as asset path
+#[derive(Deserialize)]
+struct UiThemeFile {
+    font: String,
+    palette: Palette,
+    sizes: FontSizes,
+}
+
+#[derive(Default)]
+pub struct UiThemeLoader;
+
+impl AssetLoader for UiThemeLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let file: UiThemeFile = ron::de::from_bytes(bytes)?;
+            let font_path = AssetPath::from(file.font.as_str()).to_owned();
+            let theme = UiTheme {
+                font: load_context.get_handle(font_path.clone()),
+                palette: file.palette,
+                sizes: file.sizes,
+            };
+            load_context.set_default_asset(LoadedAsset::new(theme).with_dependency(font_path));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["theme"]
+    }
+}
+
+fn select_theme(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    theme_assets: Option<Res<ThemeAssets>>,
+    themes: Res<Assets<UiTheme>>,
+    current: Option<Res<UiTheme>>,
+) {
+    let theme_assets = match theme_assets {
+        Some(theme_assets) => theme_assets,
+        None => return,
+    };
+    if let Some(theme) = themes.get(&theme_assets.get_or(&settings.theme, "default")) {
+        if current.as_deref() != Some(theme) {
+            commands.insert_resource(theme.clone());
+        }
+    }
+}
+
+/// Restyles the texts and buttons that are already on screen
+///
+/// Backgrounds, panels and gauges keep their colors until their screen is opened again.
+fn apply_theme(
+    theme: Option<Res<UiTheme>>,
+    button_materials: Res<ButtonMaterials>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+    mut texts: Query<(&ThemedText, &mut Text, Option<&mut UnscaledNode>)>,
+) {
+    let theme = match theme {
+        Some(theme) if theme.is_changed() => theme,
+        _ => return,
+    };
+    if let Some(material) = color_materials.get_mut(&button_materials.normal) {
+        material.color = theme.palette.button;
+    }
+    if let Some(material) = color_materials.get_mut(&button_materials.hovered) {
+        material.color = theme.palette.button_hovered;
+    }
+    let font_size = |themed: &ThemedText| themed.0.font_size(&theme.sizes);
+    for (themed, mut text, unscaled) in texts.iter_mut() {
+        for section in text.sections.iter_mut() {
+            section.style.font = theme.font.clone();
+            section.style.color = themed.0.color(&theme.palette);
+        }
+        match unscaled {
+            // the ui scaling applies the new sizes
+            Some(mut unscaled) => {
+                for size in unscaled.font_sizes.iter_mut() {
+                    *size = font_size(themed);
+                }
+            }
+            None => {
+                for section in text.sections.iter_mut() {
+                    section.style.font_size = font_size(themed);
+                }
+            }
+        }
+    }
+}
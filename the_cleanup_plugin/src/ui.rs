@@ -1,400 +1,581 @@
-use crate::base::Base;
+use crate::base::{Base, LAMP_UPGRADE_PRICE, MAX_LIGHT_RADIUS};
 use crate::digger::DiggerState;
+use crate::loading::TextureAssets;
+use crate::locale::Locale;
+use crate::map::Map;
+use crate::rules::{GameMode, GameRules, RunTimer};
+use crate::settings::{ControlScheme, Settings};
+use crate::statistics::score;
+use crate::theme::UiTheme;
+use crate::widgets::{spawn_label, spawn_panel, LabelStyle};
 use crate::GameState;
+use bevy::ecs::system::EntityCommands;
 use bevy::prelude::*;
 
+/// Below this share of the tank the fuel gauge starts flashing
+pub const LOW_FUEL: f32 = 0.25;
+const FUEL_FLASHES_PER_SECOND: f64 = 2.;
+const MONEY_POPUP_SECONDS: f32 = 1.5;
+const DEPTH_METER_HEIGHT: f32 = 200.;
+const DEPTH_MARKER_HEIGHT: f32 = 4.;
+
 pub struct UiPlugin;
 
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.init_resource::<ButtonMaterials>()
-            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(init_life.system()))
+        app.add_system_set(SystemSet::on_enter(GameState::Playing).with_system(init_life.system()))
             .add_system_set(
                 SystemSet::on_update(GameState::Playing)
-                    .with_system(update_game_state.system())
-                    .with_system(retry_system.system())
-                    .with_system(click_retry_button.system())
+                    .with_system(update_fuel_gauge.system())
+                    .with_system(update_money.system())
+                    .with_system(fade_money_popups.system())
+                    .with_system(update_waste_icons.system())
+                    .with_system(update_depth_meter.system())
                     .with_system(update_base_text.system())
-                    .with_system(update_waste_text.system())
-                    .with_system(won.system()),
+                    .with_system(update_mode_text.system()),
             )
             .add_system_set(SystemSet::on_exit(GameState::Playing).with_system(remove_ui.system()));
     }
 }
 
-struct ButtonMaterials {
-    normal: Handle<ColorMaterial>,
-    hovered: Handle<ColorMaterial>,
+struct Ui;
+
+struct HudMaterials {
+    gauge: Handle<ColorMaterial>,
+    gauge_low: Handle<ColorMaterial>,
+    gauge_low_dimmed: Handle<ColorMaterial>,
+    waste_collected: Handle<ColorMaterial>,
+    waste_missing: Handle<ColorMaterial>,
 }
 
-impl FromWorld for ButtonMaterials {
-    fn from_world(world: &mut World) -> Self {
-        let mut materials = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
-        ButtonMaterials {
-            normal: materials.add(Color::rgb(0.15, 0.15, 0.15).into()),
-            hovered: materials.add(Color::rgb(0.25, 0.25, 0.25).into()),
-        }
-    }
+/// The hud texts keep the values they show and are only formatted again once these change
+struct BaseText {
+    /// Whether the base was active, the light radius and the controls the text was written for
+    shown: Option<(bool, f32, ControlScheme)>,
 }
 
-struct Ui;
+/// Shows fuel and tank size
+struct FuelText {
+    shown: (f32, f32),
+}
+
+/// Filled part of the fuel gauge
+struct FuelBar;
+
+struct MoneyText {
+    shown: f32,
+}
+
+/// Column with the money counter and its gain pop-ups
+struct MoneyPopups;
 
-struct RetryButton;
+struct MoneyPopup {
+    timer: Timer,
+}
+
+/// One icon for every piece of waste needed to win
+struct WasteIcon(usize);
 
-struct BaseText;
+/// Count next to the single waste icon of modes without a waste target
+struct WasteText {
+    shown: usize,
+}
 
-struct WasteText;
+struct DepthText {
+    shown: usize,
+}
 
-struct FuelText;
+struct DepthMarker;
 
-struct MoneyText;
+struct ModeText;
 
 fn init_life(
     mut commands: Commands,
-    asset_server: ResMut<AssetServer>,
+    theme: Res<UiTheme>,
+    locale: Res<Locale>,
+    texture_assets: Res<TextureAssets>,
     digger_state: Res<DiggerState>,
+    rules: Res<GameRules>,
+    mode: Res<GameMode>,
     mut color_materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let palette = &theme.palette;
+    let mut gauge_low_dimmed = palette.gauge_low;
+    gauge_low_dimmed.set_a(palette.gauge_low.a() * 0.3);
+    let hud_materials = HudMaterials {
+        gauge: color_materials.add(palette.gauge.into()),
+        gauge_low: color_materials.add(palette.gauge_low.into()),
+        gauge_low_dimmed: color_materials.add(gauge_low_dimmed.into()),
+        waste_collected: color_materials.add(texture_assets.get("waste").into()),
+        waste_missing: color_materials.add(ColorMaterial::modulated_texture(
+            texture_assets.get("waste"),
+            Color::rgba(1., 1., 1., 0.25),
+        )),
+    };
+    let gauge_background = color_materials.add(palette.gauge_background.into());
     let material = color_materials.add(Color::NONE.into());
     commands.spawn_bundle(UiCameraBundle::default()).insert(Ui);
 
-    commands
-        .spawn_bundle(NodeBundle {
-            style: Style {
-                position_type: PositionType::Absolute,
-                position: Rect {
-                    left: Val::Px(10.),
-                    top: Val::Px(10.),
+    spawn_panel(
+        &mut commands,
+        material.clone(),
+        Rect {
+            left: Val::Px(10.),
+            top: Val::Px(10.),
+            ..Default::default()
+        },
+    )
+    .insert(Ui)
+    .with_children(|parent| {
+        spawn_label(
+            parent,
+            &theme,
+            fuel_text(&digger_state, &locale),
+            LabelStyle::Hud,
+        )
+        .insert(FuelText {
+            shown: (digger_state.fuel, digger_state.fuel_max),
+        });
+        parent
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Px(200.), Val::Px(24.)),
+                    margin: Rect {
+                        left: Val::Px(10.),
+                        ..Default::default()
+                    },
+                    padding: Rect::all(Val::Px(3.)),
+                    align_self: AlignSelf::Center,
                     ..Default::default()
                 },
+                material: gauge_background.clone(),
                 ..Default::default()
-            },
-            material: material.clone(),
-            ..Default::default()
-        })
-        .insert(Ui)
-        .with_children(|parent| {
-            parent
-                .spawn_bundle(TextBundle {
-                    text: Text {
-                        sections: vec![TextSection {
-                            value: format!(
-                                "Fuel: {}l/{}l",
-                                digger_state.fuel.round(),
-                                digger_state.fuel_max
+            })
+            .with_children(|parent| {
+                parent
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            size: Size::new(
+                                Val::Percent(fuel_percent(&digger_state)),
+                                Val::Percent(100.),
                             ),
-                            style: TextStyle {
-                                font: font.clone(),
-                                font_size: 40.0,
-                                color: Color::rgb(1., 1., 1.),
-                                ..Default::default()
-                            },
-                        }],
-                        alignment: Default::default(),
-                    },
-                    ..Default::default()
-                })
-                .insert(FuelText);
-        });
+                            ..Default::default()
+                        },
+                        material: hud_materials.gauge.clone(),
+                        ..Default::default()
+                    })
+                    .insert(FuelBar);
+            });
+    });
 
-    commands
-        .spawn_bundle(NodeBundle {
-            style: Style {
-                position_type: PositionType::Absolute,
-                position: Rect {
-                    right: Val::Px(10.),
-                    top: Val::Px(10.),
+    spawn_panel(
+        &mut commands,
+        material.clone(),
+        Rect {
+            right: Val::Px(10.),
+            top: Val::Px(10.),
+            ..Default::default()
+        },
+    )
+    .insert(Ui)
+    .with_children(|parent| {
+        parent
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::ColumnReverse,
+                    align_items: AlignItems::FlexEnd,
                     ..Default::default()
                 },
+                material: material.clone(),
                 ..Default::default()
-            },
-            material: material.clone(),
+            })
+            .insert(MoneyPopups)
+            .with_children(|parent| {
+                spawn_label(
+                    parent,
+                    &theme,
+                    locale.money(digger_state.money),
+                    LabelStyle::Hud,
+                )
+                .insert(MoneyText {
+                    shown: digger_state.money,
+                });
+            });
+    });
+
+    spawn_panel(
+        &mut commands,
+        material.clone(),
+        Rect {
+            left: Val::Px(10.),
+            top: Val::Px(50.),
             ..Default::default()
-        })
-        .insert(Ui)
-        .with_children(|parent| {
-            parent
-                .spawn_bundle(TextBundle {
-                    text: Text {
-                        sections: vec![TextSection {
-                            value: format!("$ {}", digger_state.money),
-                            style: TextStyle {
-                                font_size: 40.0,
-                                font: font.clone(),
-                                color: Color::rgb(1., 1., 1.),
-                                ..Default::default()
-                            },
-                        }],
-                        alignment: Default::default(),
-                    },
-                    ..Default::default()
-                })
-                .insert(MoneyText);
-        });
+        },
+    )
+    .insert(Ui)
+    .with_children(|parent| match rules.waste_target() {
+        Some(target) => {
+            for index in 0..target {
+                spawn_waste_icon(parent, hud_materials.waste_missing.clone())
+                    .insert(WasteIcon(index));
+            }
+        }
+        None => {
+            spawn_waste_icon(parent, hud_materials.waste_collected.clone());
+            spawn_label(
+                parent,
+                &theme,
+                waste_count(&digger_state, &locale),
+                LabelStyle::Hud,
+            )
+            .insert(WasteText {
+                shown: digger_state.waste,
+            });
+        }
+    });
+
+    spawn_panel(
+        &mut commands,
+        material.clone(),
+        Rect {
+            left: Val::Px(10.),
+            top: Val::Px(90.),
+            ..Default::default()
+        },
+    )
+    .insert(Ui)
+    .with_children(|parent| {
+        spawn_label(parent, &theme, "", LabelStyle::Hud).insert(BaseText { shown: None });
+    });
 
-    commands
-        .spawn_bundle(NodeBundle {
-            style: Style {
-                position_type: PositionType::Absolute,
-                position: Rect {
-                    right: Val::Px(10.),
-                    top: Val::Px(50.),
+    spawn_panel(
+        &mut commands,
+        material.clone(),
+        Rect {
+            right: Val::Px(10.),
+            // leaves room for the money pop-ups
+            top: Val::Px(150.),
+            ..Default::default()
+        },
+    )
+    .insert(Ui)
+    .with_children(|parent| {
+        spawn_label(
+            parent,
+            &theme,
+            depth_text(&digger_state, &locale),
+            LabelStyle::Hud,
+        )
+        .insert(DepthText {
+            shown: digger_state.depth,
+        });
+        parent
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Px(12.), Val::Px(DEPTH_METER_HEIGHT)),
+                    margin: Rect {
+                        left: Val::Px(10.),
+                        ..Default::default()
+                    },
                     ..Default::default()
                 },
+                material: gauge_background,
                 ..Default::default()
-            },
-            material: material.clone(),
-            ..Default::default()
-        })
-        .insert(Ui)
-        .with_children(|parent| {
-            parent
-                .spawn_bundle(TextBundle {
-                    text: Text {
-                        sections: vec![TextSection {
-                            value: format!("Collected waste {}/{}", digger_state.waste, 10),
-                            style: TextStyle {
-                                font_size: 40.0,
-                                font: font.clone(),
-                                color: Color::rgb(1., 1., 1.),
+            })
+            .with_children(|parent| {
+                parent
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            position: Rect {
+                                top: Val::Percent(0.),
                                 ..Default::default()
                             },
-                        }],
-                        alignment: Default::default(),
-                    },
-                    ..Default::default()
-                })
-                .insert(WasteText);
-        });
+                            size: Size::new(Val::Percent(100.), Val::Px(DEPTH_MARKER_HEIGHT)),
+                            ..Default::default()
+                        },
+                        material: hud_materials.gauge.clone(),
+                        ..Default::default()
+                    })
+                    .insert(DepthMarker);
+            });
+    });
 
-    commands
-        .spawn_bundle(NodeBundle {
-            style: Style {
-                position_type: PositionType::Absolute,
-                position: Rect {
-                    left: Val::Px(10.),
-                    top: Val::Px(50.),
-                    ..Default::default()
-                },
+    if *mode == GameMode::TimeAttack || *mode == GameMode::Endless {
+        spawn_panel(
+            &mut commands,
+            material,
+            Rect {
+                left: Val::Px(10.),
+                bottom: Val::Px(10.),
                 ..Default::default()
             },
-            material: material.clone(),
-            ..Default::default()
-        })
+        )
         .insert(Ui)
         .with_children(|parent| {
-            parent
-                .spawn_bundle(TextBundle {
-                    text: Text {
-                        sections: vec![TextSection {
-                            value: format!(""),
-                            style: TextStyle {
-                                font: font.clone(),
-                                font_size: 40.0,
-                                color: Color::rgb(1., 1., 1.),
-                                ..Default::default()
-                            },
-                        }],
-                        alignment: Default::default(),
-                    },
-                    ..Default::default()
-                })
-                .insert(BaseText);
+            spawn_label(parent, &theme, "", LabelStyle::Subtitle).insert(ModeText);
         });
+    }
+
+    commands.insert_resource(hud_materials);
+}
+
+fn spawn_waste_icon<'a, 'b, 'c>(
+    parent: &'c mut ChildBuilder<'a, 'b>,
+    material: Handle<ColorMaterial>,
+) -> EntityCommands<'a, 'c> {
+    parent.spawn_bundle(ImageBundle {
+        style: Style {
+            size: Size::new(Val::Px(24.), Val::Px(24.)),
+            margin: Rect::all(Val::Px(2.)),
+            align_self: AlignSelf::Center,
+            ..Default::default()
+        },
+        material,
+        ..Default::default()
+    })
+}
+
+/// Only touches the text if the value changed, so it is not laid out again every frame
+fn set_text(text: &mut Mut<Text>, value: String) {
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
+
+fn fuel_percent(digger_state: &DiggerState) -> f32 {
+    (digger_state.fuel / digger_state.fuel_max * 100.).clamp(0., 100.)
 }
 
-fn update_game_state(
+fn fuel_text(digger_state: &DiggerState, locale: &Locale) -> String {
+    locale.format(
+        "hud-fuel",
+        &[
+            ("fuel", locale.number(digger_state.fuel, 0)),
+            ("max", locale.number(digger_state.fuel_max, 0)),
+        ],
+    )
+}
+
+fn update_fuel_gauge(
+    time: Res<Time>,
     digger_state: Res<DiggerState>,
-    mut score_query: Query<&mut Text, (With<MoneyText>, Without<FuelText>)>,
-    mut fuel_query: Query<&mut Text, (With<FuelText>, Without<MoneyText>)>,
+    locale: Res<Locale>,
+    hud_materials: Res<HudMaterials>,
+    mut bar_query: Query<(&mut Style, &mut Handle<ColorMaterial>), With<FuelBar>>,
+    mut text_query: Query<(&mut FuelText, &mut Text)>,
 ) {
-    for mut text in score_query.iter_mut() {
-        text.sections.first_mut().unwrap().value = format!("$ {}", digger_state.money.round());
+    let low = digger_state.fuel < digger_state.fuel_max * LOW_FUEL;
+    let flash_on = (time.seconds_since_startup() * FUEL_FLASHES_PER_SECOND * 2.) as u64 % 2 == 0;
+    let wanted = match (low, flash_on) {
+        (false, _) => &hud_materials.gauge,
+        (true, true) => &hud_materials.gauge_low,
+        (true, false) => &hud_materials.gauge_low_dimmed,
+    };
+    for (mut style, mut material) in bar_query.iter_mut() {
+        if *material != *wanted {
+            *material = wanted.clone();
+        }
+        let width = Val::Percent(fuel_percent(&digger_state));
+        if style.size.width != width {
+            style.size.width = width;
+        }
     }
-    for mut text in fuel_query.iter_mut() {
-        text.sections.first_mut().unwrap().value = format!(
-            "Fuel: {}l/{}l",
-            digger_state.fuel.round(),
-            digger_state.fuel_max
-        );
+    let fuel = (digger_state.fuel, digger_state.fuel_max);
+    for (mut label, mut text) in text_query.iter_mut() {
+        if label.shown != fuel || locale.is_changed() {
+            label.shown = fuel;
+            set_text(&mut text, fuel_text(&digger_state, &locale));
+        }
     }
 }
 
-fn update_base_text(base: Res<Base>, mut base_query: Query<&mut Text, With<BaseText>>) {
-    if base.active {
-        base_query
-            .single_mut()
-            .unwrap()
-            .sections
-            .first_mut()
-            .unwrap()
-            .value = "Refueling for 1$/l".to_owned();
-    } else {
-        base_query
-            .single_mut()
-            .unwrap()
-            .sections
-            .first_mut()
-            .unwrap()
-            .value = "".to_owned();
+fn update_money(
+    mut commands: Commands,
+    digger_state: Res<DiggerState>,
+    theme: Res<UiTheme>,
+    locale: Res<Locale>,
+    mut money_query: Query<(&mut MoneyText, &mut Text)>,
+    popups_query: Query<Entity, With<MoneyPopups>>,
+) {
+    for (mut money, mut text) in money_query.iter_mut() {
+        if money.shown == digger_state.money && !locale.is_changed() {
+            continue;
+        }
+        let gain = digger_state.money - money.shown;
+        // refueling only ever spends money, so every increase was mined
+        if gain >= 0.5 {
+            for popups in popups_query.iter() {
+                commands.entity(popups).with_children(|parent| {
+                    spawn_label(
+                        parent,
+                        &theme,
+                        locale.format("hud-money-gain", &[("money", locale.money(gain))]),
+                        LabelStyle::Subtitle,
+                    )
+                    .insert(MoneyPopup {
+                        timer: Timer::from_seconds(MONEY_POPUP_SECONDS, false),
+                    });
+                });
+            }
+        }
+        money.shown = digger_state.money;
+        set_text(&mut text, locale.money(digger_state.money));
     }
 }
 
-fn update_waste_text(digger_state: Res<DiggerState>, mut query: Query<&mut Text, With<WasteText>>) {
-    for mut text in query.iter_mut() {
-        text.sections.first_mut().unwrap().value =
-            format!("Collected waste {}/{}", digger_state.waste, 10);
+fn fade_money_popups(
+    mut commands: Commands,
+    time: Res<Time>,
+    theme: Res<UiTheme>,
+    mut popup_query: Query<(Entity, &mut MoneyPopup, &mut Text)>,
+) {
+    for (entity, mut popup, mut text) in popup_query.iter_mut() {
+        popup.timer.tick(time.delta());
+        if popup.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let mut color = theme.palette.title;
+        color.set_a(color.a() * (1. - popup.timer.percent()));
+        text.sections[0].style.color = color;
     }
 }
 
-fn won(
-    mut commands: Commands,
-    asset_server: ResMut<AssetServer>,
-    mut digger_state: ResMut<DiggerState>,
-    button_materials: Res<ButtonMaterials>,
-    mut color_materials: ResMut<Assets<ColorMaterial>>,
+fn waste_count(digger_state: &DiggerState, locale: &Locale) -> String {
+    locale.format(
+        "hud-waste-count",
+        &[("waste", digger_state.waste.to_string())],
+    )
+}
+
+fn update_waste_icons(
+    digger_state: Res<DiggerState>,
+    locale: Res<Locale>,
+    hud_materials: Res<HudMaterials>,
+    mut icon_query: Query<(&WasteIcon, &mut Handle<ColorMaterial>)>,
+    mut text_query: Query<(&mut WasteText, &mut Text)>,
 ) {
-    if digger_state.dead {
-        return;
+    for (icon, mut material) in icon_query.iter_mut() {
+        let wanted = if icon.0 < digger_state.waste {
+            &hud_materials.waste_collected
+        } else {
+            &hud_materials.waste_missing
+        };
+        if *material != *wanted {
+            *material = wanted.clone();
+        }
     }
-    if digger_state.waste == 10 {
-        digger_state.mining_target = None;
-        let material = color_materials.add(Color::NONE.into());
-        digger_state.dead = true;
-        commands
-            .spawn_bundle(ButtonBundle {
-                style: Style {
-                    size: Size::new(Val::Px(150.0), Val::Px(65.0)),
-                    margin: Rect::all(Val::Auto),
-                    justify_content: JustifyContent::Center,
-                    align_items: AlignItems::Center,
-                    ..Default::default()
-                },
-                material: button_materials.normal.clone(),
-                ..Default::default()
-            })
-            .insert(RetryButton)
-            .insert(Ui)
-            .with_children(|parent| {
-                parent.spawn_bundle(TextBundle {
-                    text: Text {
-                        sections: vec![TextSection {
-                            value: "Restart".to_string(),
-                            style: TextStyle {
-                                font_size: 40.0,
-                                color: Color::rgb(0.9, 0.9, 0.9),
-                                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                                ..Default::default()
-                            },
-                        }],
-                        alignment: Default::default(),
-                    },
-                    ..Default::default()
-                });
-            });
-        commands
-            .spawn_bundle(NodeBundle {
-                style: Style {
-                    position_type: PositionType::Absolute,
-                    position: Rect {
-                        left: Val::Px(250.),
-                        bottom: Val::Px(100.),
-                        ..Default::default()
-                    },
-                    ..Default::default()
-                },
-                material: material.clone(),
-                ..Default::default()
-            })
-            .insert(Ui)
-            .with_children(|parent| {
-                parent.spawn_bundle(TextBundle {
-                    text: Text {
-                        sections: vec![TextSection {
-                            value: format!("You did it! Thank you!"),
-                            style: TextStyle {
-                                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                                font_size: 40.0,
-                                color: Color::rgb(1., 1., 1.),
-                                ..Default::default()
-                            },
-                        }],
-                        alignment: Default::default(),
-                    },
-                    ..Default::default()
-                });
-            });
+    for (mut label, mut text) in text_query.iter_mut() {
+        if label.shown != digger_state.waste || locale.is_changed() {
+            label.shown = digger_state.waste;
+            set_text(&mut text, waste_count(&digger_state, &locale));
+        }
     }
 }
 
-fn retry_system(
-    mut commands: Commands,
-    asset_server: ResMut<AssetServer>,
-    mut digger_state: ResMut<DiggerState>,
-    button_materials: Res<ButtonMaterials>,
+fn depth_text(digger_state: &DiggerState, locale: &Locale) -> String {
+    locale.format("hud-depth", &[("depth", digger_state.depth.to_string())])
+}
+
+fn update_depth_meter(
+    digger_state: Res<DiggerState>,
+    map: Res<Map>,
+    locale: Res<Locale>,
+    mut marker_query: Query<&mut Style, With<DepthMarker>>,
+    mut text_query: Query<(&mut DepthText, &mut Text)>,
 ) {
-    if digger_state.dead {
-        return;
+    let share = (digger_state.depth as f32 / map.max_depth().max(1) as f32).min(1.);
+    // the marker ends at the bottom of the meter at maximum depth
+    let top = Val::Percent(share * (100. - DEPTH_MARKER_HEIGHT / DEPTH_METER_HEIGHT * 100.));
+    for mut style in marker_query.iter_mut() {
+        if style.position.top != top {
+            style.position.top = top;
+        }
     }
-    if digger_state.fuel <= 0. {
-        digger_state.dead = true;
-        commands
-            .spawn_bundle(ButtonBundle {
-                style: Style {
-                    size: Size::new(Val::Px(150.0), Val::Px(65.0)),
-                    margin: Rect::all(Val::Auto),
-                    justify_content: JustifyContent::Center,
-                    align_items: AlignItems::Center,
-                    ..Default::default()
-                },
-                material: button_materials.normal.clone(),
-                ..Default::default()
-            })
-            .insert(RetryButton)
-            .insert(Ui)
-            .with_children(|parent| {
-                parent.spawn_bundle(TextBundle {
-                    text: Text {
-                        sections: vec![TextSection {
-                            value: "Restart".to_string(),
-                            style: TextStyle {
-                                font_size: 40.0,
-                                color: Color::rgb(0.9, 0.9, 0.9),
-                                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                                ..Default::default()
-                            },
-                        }],
-                        alignment: Default::default(),
-                    },
-                    ..Default::default()
-                });
-            });
+    for (mut label, mut text) in text_query.iter_mut() {
+        if label.shown != digger_state.depth || locale.is_changed() {
+            label.shown = digger_state.depth;
+            set_text(&mut text, depth_text(&digger_state, &locale));
+        }
     }
 }
 
-fn click_retry_button(
-    button_materials: Res<ButtonMaterials>,
-    mut state: ResMut<State<GameState>>,
-    mut digger_state: ResMut<DiggerState>,
-    mut interaction_query: Query<
-        (&Interaction, &mut Handle<ColorMaterial>),
-        (Changed<Interaction>, With<Button>),
-    >,
+fn update_base_text(
+    base: Res<Base>,
+    locale: Res<Locale>,
+    settings: Res<Settings>,
+    digger_state: Res<DiggerState>,
+    mut base_query: Query<(&mut BaseText, &mut Text)>,
 ) {
-    for (interaction, mut material) in interaction_query.iter_mut() {
-        match *interaction {
-            Interaction::Clicked => {
-                *digger_state = DiggerState::default();
-                state.set(GameState::Restart).unwrap();
-            }
-            Interaction::Hovered => {
-                *material = button_materials.hovered.clone();
-            }
-            Interaction::None => {
-                *material = button_materials.normal.clone();
+    let shown = (
+        base.active,
+        digger_state.light_radius,
+        settings.control_scheme,
+    );
+    for (mut label, mut text) in base_query.iter_mut() {
+        if label.shown == Some(shown) && !locale.is_changed() {
+            continue;
+        }
+        label.shown = Some(shown);
+        let mut value = if base.active {
+            locale.format("hud-refueling", &[("price", locale.money(1.))])
+        } else {
+            "".to_owned()
+        };
+        if base.active && digger_state.light_radius < MAX_LIGHT_RADIUS {
+            value.push_str("   ");
+            value.push_str(&locale.format(
+                "hud-lamp-upgrade",
+                &[
+                    (
+                        "key",
+                        locale.get(settings.control_scheme.upgrade_key_name()),
+                    ),
+                    ("price", locale.money(LAMP_UPGRADE_PRICE)),
+                ],
+            ));
+        }
+        set_text(&mut text, value);
+    }
+}
+
+fn update_mode_text(
+    mode: Res<GameMode>,
+    run_timer: Res<RunTimer>,
+    digger_state: Res<DiggerState>,
+    locale: Res<Locale>,
+    mut query: Query<&mut Text, With<ModeText>>,
+) {
+    let value = match *mode {
+        GameMode::TimeAttack => {
+            let mut value =
+                locale.format("hud-time", &[("time", locale.number(run_timer.elapsed, 1))]);
+            if let Some(split) = run_timer.splits.last() {
+                let previous = run_timer.splits.iter().rev().nth(1).cloned().unwrap_or(0.);
+                value.push_str("   ");
+                value.push_str(&locale.format(
+                    "hud-split",
+                    &[
+                        ("split", run_timer.splits.len().to_string()),
+                        ("time", locale.number(*split, 1)),
+                        ("difference", locale.number(split - previous, 1)),
+                    ],
+                ));
             }
+            value
         }
+        GameMode::Endless => locale.format(
+            "hud-score",
+            &[(
+                "score",
+                locale.number(score(&mode, &digger_state, false, run_timer.elapsed), 0),
+            )],
+        ),
+        _ => return,
+    };
+    for mut text in query.iter_mut() {
+        set_text(&mut text, value.clone());
     }
 }
 
@@ -0,0 +1,132 @@
+use crate::lighting::{LightMap, LightingSystemLabels};
+use crate::map::{Map, MapTile};
+use crate::GameState;
+use bevy::prelude::*;
+
+/// Brightness of tiles that were lit before but are out of the light now
+const EXPLORED_BRIGHTNESS: f32 = 0.3;
+/// Tiles lit at least this much by the headlamp or the ambient light count as explored
+const EXPLORE_THRESHOLD: f32 = 0.5;
+
+pub struct VisibilityPlugin;
+
+#[derive(SystemLabel, Eq, PartialEq, Hash, Clone, Debug)]
+pub enum VisibilitySystemLabels {
+    Explore,
+}
+
+impl Plugin for VisibilityPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_event::<TileExplored>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(setup_exploration.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(
+                        explore
+                            .system()
+                            .label(VisibilitySystemLabels::Explore)
+                            .after(LightingSystemLabels::UpdateLightMap),
+                    )
+                    .with_system(shade_tiles.system().after(VisibilitySystemLabels::Explore)),
+            );
+    }
+}
+
+/// Sent for every tile that is lit for the first time
+pub struct TileExplored {
+    pub x: usize,
+    pub y: usize,
+}
+
+/// Tiles that have been lit during this run
+pub struct ExploredTiles {
+    width: usize,
+    explored: Vec<bool>,
+}
+
+impl ExploredTiles {
+    /// Everything above the ground can be seen from the start
+    fn new(map: &Map) -> Self {
+        let mut explored_tiles = ExploredTiles {
+            width: map.width(),
+            explored: vec![false; map.width() * map.height()],
+        };
+        for y in 0..map.height() {
+            if is_sky(map, y) {
+                for x in 0..map.width() {
+                    explored_tiles.explore(x, y);
+                }
+            }
+        }
+        explored_tiles
+    }
+
+    pub fn is_explored(&self, x: usize, y: usize) -> bool {
+        self.explored[y * self.width + x]
+    }
+
+    fn explore(&mut self, x: usize, y: usize) {
+        self.explored[y * self.width + x] = true;
+    }
+}
+
+/// The sky and the base are always lit
+fn is_sky(map: &Map, y: usize) -> bool {
+    map.depth_at(y as f32 * map.tile_size) == 0
+}
+
+fn setup_exploration(mut commands: Commands, map: Res<Map>) {
+    commands.insert_resource(ExploredTiles::new(&map));
+}
+
+fn explore(
+    map: Res<Map>,
+    light_map: Res<LightMap>,
+    mut explored_tiles: ResMut<ExploredTiles>,
+    mut tile_explored: EventWriter<TileExplored>,
+) {
+    for y in 0..map.height() {
+        for x in 0..map.width() {
+            if !explored_tiles.is_explored(x, y) && light_map.lamp(x, y) >= EXPLORE_THRESHOLD {
+                explored_tiles.explore(x, y);
+                tile_explored.send(TileExplored { x, y });
+            }
+        }
+    }
+}
+
+/// Tints every tile with its light; explored tiles never get darker than a dim gray
+///
+/// Unexplored tiles only show the light reaching them, so glowing ore can be spotted in
+/// the dark and the lamp fades out softly before it explores a tile.
+fn shade_tiles(
+    map: Res<Map>,
+    light_map: Res<LightMap>,
+    explored_tiles: Res<ExploredTiles>,
+    mut tile_query: Query<(&MapTile, &mut TextureAtlasSprite)>,
+) {
+    let dim = Color::rgb(
+        EXPLORED_BRIGHTNESS,
+        EXPLORED_BRIGHTNESS,
+        EXPLORED_BRIGHTNESS,
+    );
+    for (tile, mut sprite) in tile_query.iter_mut() {
+        let color = if is_sky(&map, tile.y) {
+            Color::WHITE
+        } else if explored_tiles.is_explored(tile.x, tile.y) {
+            let light = light_map.color(tile.x, tile.y);
+            Color::rgb(
+                light.r().max(dim.r()),
+                light.g().max(dim.g()),
+                light.b().max(dim.b()),
+            )
+        } else {
+            light_map.color(tile.x, tile.y)
+        };
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use crate::base::Base;
+use crate::digger::{Digger, DiggerState, DRIVING_SPEED, FLYING_RATE};
+use crate::locale::Locale;
+use crate::map::Map;
+use crate::rules::GameRules;
+use crate::settings::Settings;
+use crate::theme::UiTheme;
+use crate::widgets::{spawn_label, LabelStyle};
+use crate::GameState;
+use bevy::prelude::*;
+
+/// Fuel needed for the way back is multiplied by this to leave room for detours
+const TURN_BACK_MARGIN: f32 = 1.5;
+/// Fuel share above a threshold before its warning can go off again
+const REARM_MARGIN: f32 = 0.05;
+const WARNING_SECONDS: f32 = 3.;
+const WARNING_FLASHES_PER_SECOND: f32 = 3.;
+
+pub struct WarningsPlugin;
+
+impl Plugin for WarningsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_event::<Warning>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(setup_warnings.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(check_fuel.system())
+                    .with_system(show_warnings.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Playing).with_system(remove_warnings.system()),
+            );
+    }
+}
+
+/// Sent once whenever a new warning goes off
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Warning {
+    /// The fuel dropped to this share of the tank
+    LowFuel(f32),
+    /// Only a little more fuel left than needed to get back to the base
+    TurnBack,
+    /// Not enough fuel left to get back to the base
+    PointOfNoReturn,
+}
+
+impl Warning {
+    fn text(&self, locale: &Locale) -> String {
+        match self {
+            Warning::LowFuel(share) => locale.format(
+                "warning-low-fuel",
+                &[("percent", locale.number(share * 100., 0))],
+            ),
+            Warning::TurnBack => locale.get("warning-turn-back"),
+            Warning::PointOfNoReturn => locale.get("warning-point-of-no-return"),
+        }
+    }
+}
+
+/// Which warnings already went off, so every warning is only given once until it is over
+#[derive(Default)]
+struct FuelWarnings {
+    thresholds: Vec<bool>,
+    turn_back: bool,
+    point_of_no_return: bool,
+}
+
+/// Full width row centering the warning text
+struct WarningBanner;
+
+/// Text in the middle of the screen showing the last warning
+struct WarningText {
+    remaining: f32,
+}
+
+/// Fuel the digger needs to climb and drive back to the base on the shortest way
+pub fn return_fuel(map: &Map, rules: &GameRules, digger: Vec2) -> f32 {
+    let climb = (map.base.y - digger.y).max(0.) / FLYING_RATE;
+    let drive = (map.base.x - digger.x).abs() / DRIVING_SPEED;
+    (climb + drive) * rules.fuel_rate
+}
+
+fn setup_warnings(
+    mut commands: Commands,
+    theme: Res<UiTheme>,
+    settings: Res<Settings>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(FuelWarnings {
+        thresholds: vec![false; settings.fuel_warnings.len()],
+        ..Default::default()
+    });
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(0.),
+                    top: Val::Percent(25.),
+                    ..Default::default()
+                },
+                size: Size::new(Val::Percent(100.), Val::Auto),
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            material: color_materials.add(Color::NONE.into()),
+            ..Default::default()
+        })
+        .insert(WarningBanner)
+        .with_children(|parent| {
+            spawn_label(parent, &theme, "", LabelStyle::Title)
+                .insert(WarningText { remaining: 0. });
+        });
+}
+
+fn check_fuel(
+    settings: Res<Settings>,
+    rules: Res<GameRules>,
+    map: Res<Map>,
+    base: Res<Base>,
+    digger_state: Res<DiggerState>,
+    mut fuel_warnings: ResMut<FuelWarnings>,
+    mut warnings: EventWriter<Warning>,
+    digger_query: Query<&Transform, With<Digger>>,
+) {
+    if rules.fuel_rate <= 0. || digger_state.dead {
+        return;
+    }
+    let share = digger_state.fuel / digger_state.fuel_max;
+    let mut lowest_crossed = None;
+    for (index, threshold) in settings.fuel_warnings.iter().enumerate() {
+        let warned = match fuel_warnings.thresholds.get_mut(index) {
+            Some(warned) => warned,
+            None => continue,
+        };
+        if share <= *threshold && !*warned {
+            *warned = true;
+            // thresholds crossed in the same frame only give one warning
+            lowest_crossed =
+                Some(lowest_crossed.map_or(*threshold, |lowest: f32| lowest.min(*threshold)));
+        } else if share > threshold + REARM_MARGIN {
+            *warned = false;
+        }
+    }
+    if let Some(threshold) = lowest_crossed {
+        warnings.send(Warning::LowFuel(threshold));
+    }
+
+    let digger = match digger_query.single() {
+        Ok(transform) => Vec2::new(transform.translation.x, transform.translation.y),
+        Err(_) => return,
+    };
+    let needed = return_fuel(&map, &rules, digger);
+    if base.active || digger_state.fuel > needed * TURN_BACK_MARGIN {
+        fuel_warnings.turn_back = false;
+        fuel_warnings.point_of_no_return = false;
+    } else if digger_state.fuel < needed {
+        if !fuel_warnings.point_of_no_return {
+            fuel_warnings.point_of_no_return = true;
+            warnings.send(Warning::PointOfNoReturn);
+        }
+    } else if !fuel_warnings.turn_back {
+        fuel_warnings.turn_back = true;
+        warnings.send(Warning::TurnBack);
+    }
+}
+
+fn show_warnings(
+    time: Res<Time>,
+    theme: Res<UiTheme>,
+    locale: Res<Locale>,
+    mut warnings: EventReader<Warning>,
+    mut text_query: Query<(&mut WarningText, &mut Text)>,
+) {
+    let warning = warnings.iter().last();
+    for (mut warning_text, mut text) in text_query.iter_mut() {
+        if let Some(warning) = warning {
+            text.sections[0].value = warning.text(&locale);
+            warning_text.remaining = WARNING_SECONDS;
+        } else if warning_text.remaining > 0. {
+            warning_text.remaining -= time.delta_seconds();
+            if warning_text.remaining <= 0. {
+                text.sections[0].value = "".to_owned();
+            }
+        }
+        if warning_text.remaining > 0. {
+            let elapsed = WARNING_SECONDS - warning_text.remaining;
+            let mut color = theme.palette.gauge_low;
+            if (elapsed * WARNING_FLASHES_PER_SECOND).fract() >= 0.5 {
+                color.set_a(color.a() * 0.4);
+            }
+            text.sections[0].style.color = color;
+        }
+    }
+}
+
+fn remove_warnings(mut commands: Commands, query: Query<Entity, With<WarningBanner>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<FuelWarnings>();
+}
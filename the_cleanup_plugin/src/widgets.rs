@@ -0,0 +1,226 @@
+use crate::navigation::{ButtonActivated, Focus, NavigationSystemLabels};
+use crate::theme::{FontSizes, Palette, UiTheme};
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use std::marker::PhantomData;
+
+pub struct WidgetsPlugin;
+
+impl Plugin for WidgetsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<ButtonMaterials>().add_system(
+            highlight_buttons
+                .system()
+                .after(NavigationSystemLabels::MoveFocus),
+        );
+    }
+}
+
+/// Sends the action component of a clicked button as event
+///
+/// Every screen uses its own action type, so a click is only ever handled by the screen it belongs to.
+pub struct ButtonActionPlugin<T>(PhantomData<T>);
+
+impl<T> Default for ButtonActionPlugin<T> {
+    fn default() -> Self {
+        ButtonActionPlugin(PhantomData)
+    }
+}
+
+impl<T: Component + Copy> Plugin for ButtonActionPlugin<T> {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_event::<T>().add_system(
+            click_buttons::<T>
+                .system()
+                .after(NavigationSystemLabels::ActivateButton),
+        );
+    }
+}
+
+pub struct ButtonMaterials {
+    pub normal: Handle<ColorMaterial>,
+    pub hovered: Handle<ColorMaterial>,
+}
+
+impl FromWorld for ButtonMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
+        ButtonMaterials {
+            normal: materials.add(Color::rgb(0.15, 0.15, 0.15).into()),
+            hovered: materials.add(Color::rgb(0.25, 0.25, 0.25).into()),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum LabelStyle {
+    Title,
+    Hud,
+    Subtitle,
+    Body,
+    Small,
+    Button,
+}
+
+impl LabelStyle {
+    pub fn font_size(&self, sizes: &FontSizes) -> f32 {
+        match self {
+            LabelStyle::Title => sizes.title,
+            LabelStyle::Hud => sizes.hud,
+            LabelStyle::Subtitle => sizes.subtitle,
+            LabelStyle::Body => sizes.body,
+            LabelStyle::Small => sizes.small,
+            LabelStyle::Button => sizes.button,
+        }
+    }
+
+    pub fn color(&self, palette: &Palette) -> Color {
+        match self {
+            LabelStyle::Title | LabelStyle::Hud | LabelStyle::Subtitle => palette.title,
+            LabelStyle::Body | LabelStyle::Small | LabelStyle::Button => palette.text,
+        }
+    }
+}
+
+/// Text that is restyled when the theme changes
+pub struct ThemedText(pub LabelStyle);
+
+pub fn text(value: impl Into<String>, theme: &UiTheme, style: LabelStyle) -> Text {
+    Text::with_section(
+        value,
+        TextStyle {
+            font: theme.font.clone(),
+            font_size: style.font_size(&theme.sizes),
+            color: style.color(&theme.palette),
+        },
+        Default::default(),
+    )
+}
+
+pub fn spawn_label<'a, 'b, 'c>(
+    parent: &'c mut ChildBuilder<'a, 'b>,
+    theme: &UiTheme,
+    value: impl Into<String>,
+    style: LabelStyle,
+) -> EntityCommands<'a, 'c> {
+    let margin = match style {
+        LabelStyle::Title => Rect::all(Val::Px(10.)),
+        _ => Rect::default(),
+    };
+    let mut label = parent.spawn_bundle(TextBundle {
+        style: Style {
+            margin,
+            // keeps the height of empty lines
+            min_size: Size::new(Val::Auto, Val::Px(style.font_size(&theme.sizes))),
+            ..Default::default()
+        },
+        text: text(value, theme, style),
+        ..Default::default()
+    });
+    label.insert(ThemedText(style));
+    label
+}
+
+pub fn spawn_button<T: Component>(
+    parent: &mut ChildBuilder,
+    button_materials: &ButtonMaterials,
+    theme: &UiTheme,
+    label: String,
+    width: f32,
+    action: T,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(width), Val::Px(50.0)),
+                margin: Rect::all(Val::Px(10.)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: button_materials.normal.clone(),
+            ..Default::default()
+        })
+        .insert(action)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: text(label, theme, LabelStyle::Button),
+                    ..Default::default()
+                })
+                .insert(ThemedText(LabelStyle::Button));
+        });
+}
+
+/// Full screen node laying out its children from top to bottom in the center
+pub fn spawn_screen<'a, 'b>(
+    commands: &'b mut Commands<'a>,
+    color_materials: &mut Assets<ColorMaterial>,
+    background: Color,
+) -> EntityCommands<'a, 'b> {
+    commands.spawn_bundle(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::ColumnReverse,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        material: color_materials.add(background.into()),
+        ..Default::default()
+    })
+}
+
+/// Invisible node at a fixed position of the window, e.g. for parts of the hud
+pub fn spawn_panel<'a, 'b>(
+    commands: &'b mut Commands<'a>,
+    material: Handle<ColorMaterial>,
+    position: Rect<Val>,
+) -> EntityCommands<'a, 'b> {
+    commands.spawn_bundle(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position,
+            ..Default::default()
+        },
+        material,
+        ..Default::default()
+    })
+}
+
+fn click_buttons<T: Component + Copy>(
+    mut actions: EventWriter<T>,
+    mut activated: EventReader<ButtonActivated>,
+    interaction_query: Query<(&Interaction, &T), (Changed<Interaction>, With<Button>)>,
+    action_query: Query<&T, With<Button>>,
+) {
+    for (interaction, action) in interaction_query.iter() {
+        if *interaction == Interaction::Clicked {
+            actions.send(*action);
+        }
+    }
+    for ButtonActivated(entity) in activated.iter() {
+        if let Ok(action) = action_query.get(*entity) {
+            actions.send(*action);
+        }
+    }
+}
+
+fn highlight_buttons(
+    focus: Res<Focus>,
+    button_materials: Res<ButtonMaterials>,
+    mut buttons: Query<(Entity, &Interaction, &mut Handle<ColorMaterial>), With<Button>>,
+) {
+    for (entity, interaction, mut material) in buttons.iter_mut() {
+        let highlighted = focus.0 == Some(entity) || *interaction == Interaction::Clicked;
+        let wanted = if highlighted {
+            &button_materials.hovered
+        } else {
+            &button_materials.normal
+        };
+        if *material != *wanted {
+            *material = wanted.clone();
+        }
+    }
+}